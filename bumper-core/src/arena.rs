@@ -0,0 +1,275 @@
+use crate::{
+    Car, Collider, ConvexPolygon, Corner, Edge, Impact, Ray, RayHit, Shape, Surface, SurfaceZone,
+};
+use serde_derive::{Deserialize, Serialize};
+
+/// Width, in px, of the playfield when none is given.
+pub const DEFAULT_ARENA_WIDTH: f64 = 1920.;
+/// Height, in px, of the playfield when none is given.
+pub const DEFAULT_ARENA_HEIGHT: f64 = 1080.;
+/// How far, in px, the boundary walls extend out of the playfield.
+pub const BOUNDARY_THICKNESS: f64 = 100.;
+
+/// A solid wall. The segment from `start` to `end` is its face; the wall fills
+/// `thickness` px behind it, opposite to [`Wall::normal`].
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Wall {
+    pub start: Corner,
+    pub end: Corner,
+    pub thickness: f64,
+}
+
+impl Wall {
+    pub fn new(start: Corner, end: Corner, thickness: f64) -> Self {
+        Wall {
+            start,
+            end,
+            thickness,
+        }
+    }
+
+    /// Unit vector out of the wall's face, the direction cars get pushed.
+    pub fn normal(&self) -> Corner {
+        Edge {
+            start: self.start,
+            end: self.end,
+        }
+        .normal()
+        .normalized()
+    }
+
+    /// The corners of the solid part of the wall.
+    pub fn vertices(&self) -> Vec<Corner> {
+        self.polygon().vertices().to_vec()
+    }
+
+    pub fn polygon(&self) -> ConvexPolygon {
+        let behind = self.normal().scale(-self.thickness);
+        ConvexPolygon::new(vec![
+            self.start,
+            self.end,
+            self.end.add(&behind),
+            self.start.add(&behind),
+        ])
+    }
+}
+
+impl Shape for Wall {
+    fn collider(&self) -> Collider {
+        Collider::Polygon(self.polygon())
+    }
+}
+
+/// The playfield: a `width` by `height` box with its top left corner at the origin,
+/// closed off by boundary walls, plus any extra walls and static obstacles inside it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Arena {
+    pub width: f64,
+    pub height: f64,
+    pub walls: Vec<Wall>,
+    /// Pillars, bumpers, barriers and islands that cars bounce off like walls.
+    #[serde(default)]
+    pub obstacles: Vec<Collider>,
+    /// How bouncy the walls are, from 0 to 1. Averaged with the car's own restitution.
+    pub restitution: f64,
+    /// Patches of floor that handle differently, like ice or mud. Where zones
+    /// overlap, the later one wins.
+    #[serde(default)]
+    pub surfaces: Vec<SurfaceZone>,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Arena::new(DEFAULT_ARENA_WIDTH, DEFAULT_ARENA_HEIGHT)
+    }
+}
+
+impl Arena {
+    pub fn new(width: f64, height: f64) -> Self {
+        let corners = [
+            Corner { x: 0., y: 0. },
+            Corner { x: width, y: 0. },
+            Corner {
+                x: width,
+                y: height,
+            },
+            Corner { x: 0., y: height },
+        ];
+        // Walk the boundary so that every wall faces into the playfield.
+        let walls = (0..corners.len())
+            .map(|i| {
+                Wall::new(
+                    corners[i],
+                    corners[(i + 1) % corners.len()],
+                    BOUNDARY_THICKNESS,
+                )
+            })
+            .collect();
+
+        Arena {
+            width,
+            height,
+            walls,
+            obstacles: Vec::new(),
+            restitution: 0.5,
+            surfaces: Vec::new(),
+        }
+    }
+
+    pub fn with_wall(mut self, wall: Wall) -> Self {
+        self.walls.push(wall);
+        self
+    }
+
+    pub fn with_obstacle<S: Shape>(mut self, obstacle: S) -> Self {
+        self.obstacles.push(obstacle.collider());
+        self
+    }
+
+    pub fn with_surface(mut self, zone: SurfaceZone) -> Self {
+        self.surfaces.push(zone);
+        self
+    }
+
+    /// The floor at `point`.
+    pub fn surface_at(&self, point: &Corner) -> Surface {
+        self.surfaces
+            .iter()
+            .rev()
+            .find(|zone| zone.contains(point))
+            .map(SurfaceZone::surface)
+            .unwrap_or_default()
+    }
+
+    /// Every solid shape in the arena: the walls, then the obstacles.
+    pub fn solids(&self) -> impl Iterator<Item = Collider> + '_ {
+        self.walls
+            .iter()
+            .map(Wall::collider)
+            .chain(self.obstacles.iter().cloned())
+    }
+
+    /// The nearest wall or obstacle `ray` hits within `max_distance`, with its
+    /// index in [`Arena::solids`] order.
+    pub fn raycast(&self, ray: &Ray, max_distance: f64) -> Option<(usize, RayHit)> {
+        self.solids()
+            .enumerate()
+            .filter_map(|(index, solid)| Some((index, solid.raycast(ray)?)))
+            .filter(|(_, hit)| hit.distance <= max_distance)
+            .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+    }
+
+    /// Whether `car` overlaps any wall or obstacle.
+    pub fn collides(&self, car: &Car) -> bool {
+        let bounds = car.aabb();
+        self.solids()
+            .any(|solid| solid.aabb().overlaps(&bounds) && solid.overlaps(car))
+    }
+
+    pub fn with_restitution(self, restitution: f64) -> Self {
+        Arena {
+            restitution,
+            ..self
+        }
+    }
+
+    /// Whether the point lies inside the playfield.
+    pub fn contains(&self, point: &Corner) -> bool {
+        (0. ..=self.width).contains(&point.x) && (0. ..=self.height).contains(&point.y)
+    }
+
+    /// Advance `car` by `dt` seconds without letting it skip through a wall or
+    /// obstacle, then bounce it off whatever it ran into. Returns whether it
+    /// touched anything.
+    pub fn update_car(&self, car: &mut Car, dt: f64) -> bool {
+        let solids = self.solids().collect::<Vec<_>>();
+        let surface = self.surface_at(&car.center());
+        let stopped = car.update_swept_on(dt, &surface, &solids).is_some();
+        self.collide(car) || stopped
+    }
+
+    /// Push `car` out of every wall and obstacle it overlaps and bounce it off them.
+    /// Returns whether it touched any.
+    pub fn collide(&self, car: &mut Car) -> bool {
+        !self.impacts(car).is_empty()
+    }
+
+    /// Like [`Arena::collide`], but describes every impact along with the index,
+    /// in [`Arena::solids`] order, of what the car hit.
+    pub fn impacts(&self, car: &mut Car) -> Vec<(usize, Impact)> {
+        let mut impacts = Vec::new();
+        for (index, solid) in self.solids().enumerate() {
+            if !solid.aabb().overlaps(&car.aabb()) {
+                continue;
+            }
+            if let Some(contact) = solid.contact_with(&*car) {
+                impacts.push((index, car.impact_static(&contact, self.restitution)));
+            }
+        }
+        impacts
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Circle, Rectangle, FRAME_DT};
+
+    #[test]
+    fn test_car_bounces_off_boundary() {
+        let arena = Arena::new(800., 600.);
+        let mut car = Car::new(400., 50., 60., 80.);
+        car.control.forward = true;
+
+        let mut bounced = false;
+        for _ in 0..120 {
+            car.update(FRAME_DT);
+            bounced |= arena.collide(&mut car);
+            assert!(car.y - car.height / 2. > -1e-6);
+        }
+        assert!(bounced);
+    }
+
+    #[test]
+    fn test_car_bounces_off_obstacle() {
+        let pillar = Rectangle::new(400., 300., 40., 40., std::f64::consts::FRAC_PI_4).polygon();
+        let arena = Arena::default().with_obstacle(pillar);
+        let mut car = Car::new(400., 400., 60., 80.);
+        car.config.speed = 300.;
+
+        let mut bounced = false;
+        for _ in 0..60 {
+            car.update(FRAME_DT);
+            bounced |= arena.collide(&mut car);
+        }
+        assert!(bounced);
+        assert!(car.y > 300.);
+        assert!(!arena.collides(&car));
+    }
+
+    #[test]
+    fn test_car_bounces_off_round_bumper() {
+        let bumper = Circle::new(Corner { x: 400., y: 300. }, 30.);
+        let arena = Arena::default().with_obstacle(bumper);
+        let mut car = Car::new(400., 400., 60., 80.);
+        car.config.speed = 300.;
+
+        let mut bounced = false;
+        for _ in 0..60 {
+            car.update(FRAME_DT);
+            bounced |= arena.collide(&mut car);
+        }
+        assert!(bounced);
+        assert!(car.y > 330.);
+    }
+
+    #[test]
+    fn test_car_in_the_middle_is_left_alone() {
+        let arena = Arena::default();
+        let mut car = Car::new(400., 400., 60., 80.);
+        car.config.speed = 100.;
+        assert!(!arena.collide(&mut car));
+        assert_eq!(car.config.speed, 100.);
+        assert!(arena.contains(&Corner { x: car.x, y: car.y }));
+    }
+}
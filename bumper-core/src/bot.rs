@@ -0,0 +1,230 @@
+use crate::{Autopilot, Car, Control, Corner, Driver, Lidar, Observation, SplitMix64};
+use serde_derive::{Deserialize, Serialize};
+
+/// How good a [`Bot`] is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Medium,
+    Hard,
+}
+
+impl Difficulty {
+    /// Seconds between a bot looking for its target and acting on what it saw.
+    pub fn reaction_time(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.6,
+            Difficulty::Medium => 0.3,
+            Difficulty::Hard => 0.1,
+        }
+    }
+
+    /// Largest angle, in radians, a bot can be off by when aiming.
+    pub fn aim_error(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Medium => 0.25,
+            Difficulty::Hard => 0.05,
+        }
+    }
+
+    /// Fraction of the car's top speed a bot drives at.
+    pub fn cruise(self) -> f64 {
+        match self {
+            Difficulty::Easy => 0.5,
+            Difficulty::Medium => 0.7,
+            Difficulty::Hard => 0.9,
+        }
+    }
+}
+
+/// What a [`Bot`] is trying to do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Behaviour {
+    /// Ram the nearest car, steering around walls and obstacles on the way.
+    Chaser,
+    /// Keep away from the nearest car.
+    Evader,
+    /// Just drive around without hitting anything.
+    WallAvoider,
+}
+
+/// A rule-based computer player. It only looks for its target every
+/// `reaction_time` seconds, so it chases or flees where the target was rather than
+/// where it is, and each look is off by up to `aim_error` radians. Walls and
+/// obstacles are dodged with an [`Autopilot`] on every step regardless.
+#[derive(Debug, Clone)]
+pub struct Bot {
+    pub behaviour: Behaviour,
+    pub reaction_time: f64,
+    pub aim_error: f64,
+    pub autopilot: Autopilot,
+    rng: SplitMix64,
+    /// Where the bot is heading for, and when it should look again.
+    aim: Option<(Corner, f64)>,
+}
+
+impl Bot {
+    /// `seed` picks the bot's aim errors, so the same seed plays the same way.
+    pub fn new(behaviour: Behaviour, difficulty: Difficulty, seed: u64) -> Self {
+        Bot {
+            behaviour,
+            reaction_time: difficulty.reaction_time(),
+            aim_error: difficulty.aim_error(),
+            autopilot: Autopilot {
+                cruise: difficulty.cruise(),
+                ..Default::default()
+            },
+            rng: SplitMix64::new(seed),
+            aim: None,
+        }
+    }
+
+    pub fn with_reaction_time(self, reaction_time: f64) -> Self {
+        Bot {
+            reaction_time,
+            ..self
+        }
+    }
+
+    pub fn with_aim_error(self, aim_error: f64) -> Self {
+        Bot { aim_error, ..self }
+    }
+
+    /// Pick a point to drive at: at the nearest car when chasing, straight away
+    /// from it when evading, off by a random angle either way.
+    fn look(&mut self, observation: &Observation) -> Option<Corner> {
+        let me = observation.car.center();
+        let nearest = observation
+            .others()
+            .map(|(_, car)| car.center())
+            .min_by(|a, b| a.sub(&me).length().total_cmp(&b.sub(&me).length()))?;
+        let towards = match self.behaviour {
+            Behaviour::Chaser => nearest.sub(&me),
+            Behaviour::Evader => me.sub(&nearest),
+            Behaviour::WallAvoider => return None,
+        };
+        let error = self.rng.range(-self.aim_error, self.aim_error);
+        let (sin, cos) = error.sin_cos();
+        let off = Corner {
+            x: towards.x * cos - towards.y * sin,
+            y: towards.x * sin + towards.y * cos,
+        };
+        Some(me.add(&off))
+    }
+}
+
+impl Driver for Bot {
+    fn drive(&mut self, observation: &Observation) -> Control {
+        let now = observation.world.time;
+        let looked = match self.aim {
+            Some((_, next_look)) if now < next_look => self.aim,
+            _ => self
+                .look(observation)
+                .map(|point| (point, now + self.reaction_time)),
+        };
+        self.aim = looked;
+
+        let car = observation.car;
+        let readings = match self.behaviour {
+            // A chaser means to hit cars, so it only looks out for the arena.
+            Behaviour::Chaser => {
+                let lidar = car.lidar.as_ref().unwrap_or(&self.autopilot.lidar);
+                scan_arena(lidar, observation)
+            }
+            _ => self.autopilot.readings(observation),
+        };
+        let steering = self
+            .aim
+            .map_or(0., |(point, _)| steering_towards(car, &point));
+        self.autopilot.avoid(car, &readings, steering)
+    }
+}
+
+fn scan_arena(lidar: &Lidar, observation: &Observation) -> Vec<f64> {
+    lidar.scan(observation.car, |ray| {
+        observation
+            .world
+            .arena
+            .raycast(ray, lidar.range)
+            .map(|(_, hit)| hit.distance)
+    })
+}
+
+/// Steering, in `[-1, 1]`, that turns `car` to face `point`, at full lock when
+/// it's more than half a radian off.
+pub fn steering_towards(car: &Car, point: &Corner) -> f64 {
+    let towards = point.sub(&car.center());
+    let off = towards.dot(&car.right()).atan2(towards.dot(&car.heading()));
+    (off * 2.).clamp(-1., 1.)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Arena, CarId, Struck, World, WorldEvent};
+
+    fn run(world: &mut World, drivers: &mut [(CarId, Bot)], seconds: f64) -> Vec<WorldEvent> {
+        let mut events = Vec::new();
+        for _ in 0..(seconds * 60.) as usize {
+            for (id, bot) in drivers.iter_mut() {
+                world.drive(*id, bot);
+            }
+            world.step();
+            events.extend(world.drain_events());
+        }
+        events
+    }
+
+    #[test]
+    fn test_harder_bots_react_faster_and_aim_better() {
+        let (easy, hard) = (Difficulty::Easy, Difficulty::Hard);
+        assert!(easy.reaction_time() > Difficulty::Medium.reaction_time());
+        assert!(hard.reaction_time() < Difficulty::Medium.reaction_time());
+        assert!(easy.aim_error() > hard.aim_error());
+        assert!(easy.cruise() < hard.cruise());
+    }
+
+    #[test]
+    fn test_chaser_rams_and_evader_flees() {
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let mut world = World::new(Arena::new(1500., 1000.));
+            let chaser = world.add_car(Car::new(300., 500., 60., 80.));
+            let target = world.add_car(Car::new(900., 300., 60., 80.));
+            let mut drivers = [(chaser, Bot::new(Behaviour::Chaser, difficulty, 1))];
+            let rammed = run(&mut world, &mut drivers, 10.).iter().any(|event| {
+                matches!(event, WorldEvent::Collision(collision)
+                    if collision.involves(chaser) && collision.involves(target))
+            });
+            assert!(rammed, "{:?} chaser never hit its target.", difficulty);
+        }
+
+        let mut world = World::new(Arena::new(1500., 1000.));
+        let evader = world.add_car(Car::new(700., 500., 60., 80.));
+        let other = world.add_car(Car::new(500., 500., 60., 80.));
+        let mut drivers = [(evader, Bot::new(Behaviour::Evader, Difficulty::Hard, 1))];
+        run(&mut world, &mut drivers, 1.5);
+        let gap = world
+            .car(evader)
+            .unwrap()
+            .center()
+            .sub(&world.car(other).unwrap().center())
+            .length();
+        assert!(gap > 400.);
+    }
+
+    #[test]
+    fn test_wall_avoider_keeps_off_the_walls() {
+        let mut world = World::new(Arena::new(1000., 1000.));
+        let id = world.add_car(Car::new(500., 200., 60., 80.));
+        let mut drivers = [(id, Bot::new(Behaviour::WallAvoider, Difficulty::Hard, 7))];
+        for event in run(&mut world, &mut drivers, 20.) {
+            if let WorldEvent::Collision(collision) = event {
+                assert!(!matches!(collision.other, Struck::Wall { .. }));
+            }
+        }
+    }
+}
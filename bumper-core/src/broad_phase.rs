@@ -0,0 +1,177 @@
+use crate::Corner;
+use serde_derive::{Deserialize, Serialize};
+
+/// An axis-aligned bounding box.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Aabb {
+    pub min: Corner,
+    pub max: Corner,
+}
+
+impl Aabb {
+    pub fn new(min: Corner, max: Corner) -> Self {
+        Aabb { min, max }
+    }
+
+    /// The smallest box containing every one of `vertices`.
+    pub fn from_vertices(vertices: &[Corner]) -> Self {
+        let (min_x, max_x) = Corner { x: 1., y: 0. }.project(vertices);
+        let (min_y, max_y) = Corner { x: 0., y: 1. }.project(vertices);
+        Aabb {
+            min: Corner { x: min_x, y: min_y },
+            max: Corner { x: max_x, y: max_y },
+        }
+    }
+
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.min.x <= other.max.x
+            && other.min.x <= self.max.x
+            && self.min.y <= other.max.y
+            && other.min.y <= self.max.y
+    }
+
+    /// This box grown by `margin` on every side.
+    pub fn expanded(&self, margin: f64) -> Aabb {
+        Aabb {
+            min: Corner {
+                x: self.min.x - margin,
+                y: self.min.y - margin,
+            },
+            max: Corner {
+                x: self.max.x + margin,
+                y: self.max.y + margin,
+            },
+        }
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: Corner {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+            },
+            max: Corner {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+            },
+        }
+    }
+}
+
+/// Sweep-and-prune over bounding boxes. Cheaply narrows "everything against
+/// everything" down to the pairs whose boxes overlap, which are the only ones
+/// worth handing to the exact (and much more expensive) narrow phase.
+#[derive(Debug, Clone)]
+pub struct BroadPhase<K> {
+    entries: Vec<(K, Aabb)>,
+}
+
+impl<K> Default for BroadPhase<K> {
+    fn default() -> Self {
+        BroadPhase {
+            entries: Vec::new(),
+        }
+    }
+}
+
+impl<K: Copy> BroadPhase<K> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: K, aabb: Aabb) {
+        self.entries.push((key, aabb));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every pair of keys whose boxes overlap, each pair reported once.
+    pub fn pairs(&mut self) -> Vec<(K, K)> {
+        self.entries
+            .sort_by(|(_, a), (_, b)| a.min.x.total_cmp(&b.min.x));
+
+        let mut pairs = Vec::new();
+        for (i, (key_a, aabb_a)) in self.entries.iter().enumerate() {
+            for (key_b, aabb_b) in &self.entries[i + 1..] {
+                // Sorted by left edge, so nothing further along can reach back.
+                if aabb_b.min.x > aabb_a.max.x {
+                    break;
+                }
+                if aabb_a.overlaps(aabb_b) {
+                    pairs.push((*key_a, *key_b));
+                }
+            }
+        }
+        pairs
+    }
+
+    /// Every key whose box overlaps `aabb`.
+    pub fn query(&self, aabb: &Aabb) -> Vec<K> {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.overlaps(aabb))
+            .map(|(key, _)| *key)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Car, Shape};
+
+    #[test]
+    fn test_pairs_match_brute_force() {
+        // A cheap deterministic scatter of cars, many of them overlapping.
+        let mut seed = 12345_u64;
+        let mut next = || {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1);
+            (seed >> 33) as f64 / (1_u64 << 31) as f64
+        };
+        let cars = (0..300)
+            .map(|_| Car::new(next() * 2000., next() * 2000., 60., 80.).with_angle(next() * 6.))
+            .collect::<Vec<_>>();
+
+        let mut broad_phase = BroadPhase::new();
+        for (i, car) in cars.iter().enumerate() {
+            broad_phase.insert(i, car.aabb());
+        }
+        let mut pairs = broad_phase
+            .pairs()
+            .into_iter()
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect::<Vec<_>>();
+        pairs.sort();
+
+        let mut expected = Vec::new();
+        for i in 0..cars.len() {
+            for j in i + 1..cars.len() {
+                if cars[i].aabb().overlaps(&cars[j].aabb()) {
+                    expected.push((i, j));
+                }
+            }
+        }
+        assert!(!expected.is_empty());
+        assert_eq!(pairs, expected);
+
+        // Every real collision survives the broad phase.
+        for i in 0..cars.len() {
+            for j in i + 1..cars.len() {
+                if cars[i].collides(&cars[j]) {
+                    assert!(pairs.binary_search(&(i, j)).is_ok());
+                }
+            }
+        }
+    }
+}
@@ -1,35 +1,317 @@
-use crate::Rectangle;
+use crate::{Aabb, Collider, Contact, Corner, FixedTimestep, Lidar, Rectangle, Shape, Surface};
 use serde_derive::{Deserialize, Serialize};
 
+/// The timestep, in seconds, that the original per-frame tuning assumed.
+pub const FRAME_DT: f64 = 1. / 60.;
+
+/// Spin slower than this, in rad/s, is considered stopped.
+const MIN_ANGULAR_SPEED: f64 = 1e-2;
+
+/// Sliding slower than this, in px/s, is considered stopped.
+const MIN_LATERAL_SPEED: f64 = 1.;
+
+/// Driving parameters of a car.
 ///
-///
+/// Every quantity is expressed per second (speeds in px/s, accelerations in px/s²
+/// and turn rates in rad/s), so that [`Car::update`] behaves the same regardless
+/// of how often it is called.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct CarConfig {
+    /// Velocity along the car's heading.
     pub speed: f64,
+    /// Velocity sideways to the car's heading, positive towards its right.
+    pub lateral_speed: f64,
     pub acceleration: f64,
     pub max_speed: f64,
     pub friction: f64,
+    /// Heading, in radians, anticlockwise on screen from straight up. The car
+    /// drives along `(-sin(angle), -cos(angle))`.
     pub angle: f64,
     pub angle_delta: f64,
+    /// How fast the car is spinning, in rad/s, in the same sense as `angle`.
+    pub angular_speed: f64,
+    /// How quickly spinning dies down, as an exponential decay rate per second.
+    pub angular_damping: f64,
+    /// How hard the car is to knock around. A non-positive or infinite mass makes it immovable.
+    pub mass: f64,
+    /// How much of the closing speed survives a collision, from 0 (dead stop) to 1 (perfect bounce).
+    pub restitution: f64,
+    /// How quickly the tyres kill sideways sliding, as an exponential decay rate per second.
+    /// Low values let the car drift; high values make it corner on rails.
+    pub grip: f64,
+    /// Fraction of `grip` left while the handbrake is pulled. A car is a single
+    /// body here, with no separate axles, so this loosens the whole car rather than
+    /// just the locked rear wheels; `handbrake_steering` stands in for the tail
+    /// stepping out.
+    pub handbrake_grip: f64,
+    /// How much harder the car turns while the handbrake is pulled.
+    pub handbrake_steering: f64,
+    /// Seconds of boost a full meter holds.
+    pub boost_capacity: f64,
+    /// Seconds of boost regained per second spent not boosting.
+    pub boost_recharge: f64,
+    /// How much boosting multiplies `acceleration` and `max_speed` by.
+    pub boost_factor: f64,
+}
+
+impl CarConfig {
+    /// Convert a config tuned in per-frame units (as applied once per call by the old
+    /// `update`) into per-second units, assuming frames that are `dt` seconds apart.
+    pub fn from_per_frame(per_frame: CarConfig, dt: f64) -> Self {
+        CarConfig {
+            speed: per_frame.speed / dt,
+            lateral_speed: per_frame.lateral_speed / dt,
+            acceleration: per_frame.acceleration / (dt * dt),
+            max_speed: per_frame.max_speed / dt,
+            friction: per_frame.friction / (dt * dt),
+            angle: per_frame.angle,
+            angle_delta: per_frame.angle_delta / dt,
+            angular_speed: per_frame.angular_speed / dt,
+            ..per_frame
+        }
+    }
+
+    /// Start building a config from the defaults.
+    pub fn builder() -> CarConfigBuilder {
+        CarConfigBuilder::default()
+    }
+
+    /// Check every field is in a range that makes sense, returning the first that isn't.
+    pub fn validate(&self) -> Result<(), CarConfigError> {
+        finite("speed", self.speed)?;
+        finite("lateral_speed", self.lateral_speed)?;
+        at_least("acceleration", self.acceleration, 0.)?;
+        if finite("max_speed", self.max_speed)? <= 0. {
+            return Err(CarConfigError::NotPositive { field: "max_speed" });
+        }
+        at_least("friction", self.friction, 0.)?;
+        finite("angle", self.angle)?;
+        at_least("angle_delta", self.angle_delta, 0.)?;
+        finite("angular_speed", self.angular_speed)?;
+        at_least("angular_damping", self.angular_damping, 0.)?;
+        // An infinite mass is fine: that's an immovable car.
+        if self.mass.is_nan() {
+            return Err(CarConfigError::NotFinite { field: "mass" });
+        }
+        if self.mass <= 0. {
+            return Err(CarConfigError::NotPositive { field: "mass" });
+        }
+        within("restitution", self.restitution, 0., 1.)?;
+        at_least("grip", self.grip, 0.)?;
+        within("handbrake_grip", self.handbrake_grip, 0., 1.)?;
+        at_least("handbrake_steering", self.handbrake_steering, 0.)?;
+        at_least("boost_capacity", self.boost_capacity, 0.)?;
+        at_least("boost_recharge", self.boost_recharge, 0.)?;
+        at_least("boost_factor", self.boost_factor, 1.)?;
+        Ok(())
+    }
+
+    pub fn inverse_mass(&self) -> f64 {
+        if self.mass > 0. && self.mass.is_finite() {
+            1. / self.mass
+        } else {
+            0.
+        }
+    }
+}
+
+/// Builds a [`CarConfig`] from [`CarConfig::default`], checking every value on
+/// [`CarConfigBuilder::build`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CarConfigBuilder {
+    config: CarConfig,
+}
+
+impl CarConfigBuilder {
+    pub fn acceleration(mut self, acceleration: f64) -> Self {
+        self.config.acceleration = acceleration;
+        self
+    }
+
+    pub fn max_speed(mut self, max_speed: f64) -> Self {
+        self.config.max_speed = max_speed;
+        self
+    }
+
+    pub fn friction(mut self, friction: f64) -> Self {
+        self.config.friction = friction;
+        self
+    }
+
+    pub fn angle(mut self, angle: f64) -> Self {
+        self.config.angle = angle;
+        self
+    }
+
+    pub fn angle_delta(mut self, angle_delta: f64) -> Self {
+        self.config.angle_delta = angle_delta;
+        self
+    }
+
+    pub fn angular_damping(mut self, angular_damping: f64) -> Self {
+        self.config.angular_damping = angular_damping;
+        self
+    }
+
+    pub fn mass(mut self, mass: f64) -> Self {
+        self.config.mass = mass;
+        self
+    }
+
+    pub fn restitution(mut self, restitution: f64) -> Self {
+        self.config.restitution = restitution;
+        self
+    }
+
+    pub fn grip(mut self, grip: f64) -> Self {
+        self.config.grip = grip;
+        self
+    }
+
+    pub fn handbrake_grip(mut self, handbrake_grip: f64) -> Self {
+        self.config.handbrake_grip = handbrake_grip;
+        self
+    }
+
+    pub fn handbrake_steering(mut self, handbrake_steering: f64) -> Self {
+        self.config.handbrake_steering = handbrake_steering;
+        self
+    }
+
+    pub fn boost_capacity(mut self, boost_capacity: f64) -> Self {
+        self.config.boost_capacity = boost_capacity;
+        self
+    }
+
+    pub fn boost_recharge(mut self, boost_recharge: f64) -> Self {
+        self.config.boost_recharge = boost_recharge;
+        self
+    }
+
+    pub fn boost_factor(mut self, boost_factor: f64) -> Self {
+        self.config.boost_factor = boost_factor;
+        self
+    }
+
+    /// The finished config, or the first value that is out of range.
+    pub fn build(self) -> Result<CarConfig, CarConfigError> {
+        self.config.validate()?;
+        Ok(self.config)
+    }
+}
+
+/// Why a [`CarConfig`] was rejected.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CarConfigError {
+    /// The field is NaN or infinite.
+    NotFinite {
+        field: &'static str,
+    },
+    /// The field has to be greater than zero.
+    NotPositive {
+        field: &'static str,
+    },
+    TooSmall {
+        field: &'static str,
+        min: f64,
+    },
+    TooLarge {
+        field: &'static str,
+        max: f64,
+    },
+}
+
+impl CarConfigError {
+    /// The name of the offending field.
+    pub fn field(&self) -> &'static str {
+        match self {
+            CarConfigError::NotFinite { field }
+            | CarConfigError::NotPositive { field }
+            | CarConfigError::TooSmall { field, .. }
+            | CarConfigError::TooLarge { field, .. } => field,
+        }
+    }
+}
+
+impl std::fmt::Display for CarConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CarConfigError::NotFinite { field } => write!(f, "{} must be a finite number.", field),
+            CarConfigError::NotPositive { field } => write!(f, "{} must be greater than 0.", field),
+            CarConfigError::TooSmall { field, min } => {
+                write!(f, "{} must be at least {}.", field, min)
+            }
+            CarConfigError::TooLarge { field, max } => {
+                write!(f, "{} must be at most {}.", field, max)
+            }
+        }
+    }
+}
+
+impl std::error::Error for CarConfigError {}
+
+fn finite(field: &'static str, value: f64) -> Result<f64, CarConfigError> {
+    if value.is_finite() {
+        Ok(value)
+    } else {
+        Err(CarConfigError::NotFinite { field })
+    }
+}
+
+fn at_least(field: &'static str, value: f64, min: f64) -> Result<(), CarConfigError> {
+    if finite(field, value)? < min {
+        return Err(CarConfigError::TooSmall { field, min });
+    }
+    Ok(())
+}
+
+fn within(field: &'static str, value: f64, min: f64, max: f64) -> Result<(), CarConfigError> {
+    at_least(field, value, min)?;
+    if value > max {
+        return Err(CarConfigError::TooLarge { field, max });
+    }
+    Ok(())
 }
 
 impl Default for CarConfig {
     fn default() -> Self {
+        let per_second = CarConfig::from_per_frame(
+            CarConfig {
+                speed: 0.0,
+                lateral_speed: 0.0,
+                acceleration: 0.2,
+                max_speed: 10.0,
+                friction: 0.05,
+                angle: 0.0,
+                angle_delta: 0.03,
+                angular_speed: 0.0,
+                angular_damping: 0.0,
+                mass: 1.0,
+                restitution: 0.8,
+                grip: 0.0,
+                handbrake_grip: 0.2,
+                handbrake_steering: 1.5,
+                boost_capacity: 0.0,
+                boost_recharge: 0.0,
+                boost_factor: 1.5,
+            },
+            FRAME_DT,
+        );
         CarConfig {
-            speed: 0.0,
-            acceleration: 0.2,
-            max_speed: 10.0,
-            friction: 0.05,
-            angle: 0.0,
-            angle_delta: 0.03,
+            grip: 10.0,
+            angular_damping: 3.0,
+            boost_capacity: 2.0,
+            boost_recharge: 0.25,
+            ..per_second
         }
     }
 }
 
 impl std::fmt::Display for CarConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "CarConfig {{ speed: {}, acceleration: {}, max_speed: {}, friction: {}, angle: {}, angle_delta: {} }}",
-               self.speed, self.acceleration, self.max_speed, self.friction, self.angle, self.angle_delta)
+        write!(f, "CarConfig {{ speed: {}, lateral_speed: {}, acceleration: {}, max_speed: {}, friction: {}, angle: {}, angle_delta: {}, angular_speed: {}, angular_damping: {}, mass: {}, restitution: {}, grip: {}, handbrake_grip: {}, handbrake_steering: {}, boost_capacity: {}, boost_recharge: {}, boost_factor: {} }}",
+               self.speed, self.lateral_speed, self.acceleration, self.max_speed, self.friction, self.angle, self.angle_delta, self.angular_speed, self.angular_damping, self.mass, self.restitution, self.grip, self.handbrake_grip, self.handbrake_steering, self.boost_capacity, self.boost_recharge, self.boost_factor)
     }
 }
 
@@ -42,11 +324,22 @@ pub struct Car {
     pub height: f64,
     pub config: CarConfig,
     pub control: Control,
+    /// Seconds of boost left in the meter. This is the car's own state rather than
+    /// tuning, so it lives outside `config`.
+    #[serde(default)]
+    pub boost: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lidar: Option<Lidar>,
 }
 
 impl Car {
+    /// The same car with different tuning, and a full boost meter to match.
     pub fn with_config(self, config: CarConfig) -> Self {
-        Car { config, ..self }
+        Car {
+            config,
+            boost: config.boost_capacity,
+            ..self
+        }
     }
     pub fn with_angle(self, angle: f64) -> Self {
         let config = CarConfig {
@@ -67,11 +360,25 @@ impl Car {
             height,
             config: CarConfig::default(),
             control: Control::default(),
+            boost: CarConfig::default().boost_capacity,
+            lidar: None,
+        }
+    }
+
+    /// Where the car's centre is.
+    pub fn center(&self) -> Corner {
+        Corner {
+            x: self.x,
+            y: self.y,
         }
     }
 
-    pub fn json(&self) -> String {
-        serde_json::to_string(self).unwrap()
+    pub fn json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> Result<Car, serde_json::Error> {
+        serde_json::from_str(json)
     }
 }
 
@@ -91,119 +398,762 @@ pub struct CarPosition {
     pub y: f64,
     pub width: f64,
     pub height: f64,
+    /// Heading in the same sense as [`CarConfig::angle`].
     pub angle: f64,
 }
 
 impl Car {
-    pub fn update(&mut self) {
-        if self.control.forward {
-            self.config.speed += self.config.acceleration;
-        }
-        if self.control.reverse {
-            self.config.speed -= self.config.acceleration;
-        }
+    /// Advance the car by `dt` seconds.
+    pub fn update(&mut self, dt: f64) {
+        self.update_on(dt, &Surface::default())
+    }
+
+    /// Like [`Car::update`], but driving on `surface`.
+    pub fn update_on(&mut self, dt: f64, surface: &Surface) {
+        let boosting = self.control.boost && self.boost > 0.;
+        let boost = if boosting {
+            self.boost = (self.boost - dt).max(0.);
+            self.config.boost_factor
+        } else {
+            self.boost =
+                (self.boost + self.config.boost_recharge * dt).min(self.config.boost_capacity);
+            1.
+        };
+        // The handbrake loosens every wheel at once: see `CarConfig::handbrake_grip`.
+        let (grip, steering) = if self.control.handbrake {
+            (
+                self.config.grip * self.config.handbrake_grip,
+                self.config.handbrake_steering,
+            )
+        } else {
+            (self.config.grip, 1.)
+        };
+        let grip = grip * surface.grip;
+
+        let acceleration = self.config.acceleration * boost * surface.acceleration * dt;
+        let max_speed = self.config.max_speed * boost * surface.max_speed;
+        let friction = self.config.friction * surface.friction * dt;
 
-        if self.config.speed > self.config.max_speed {
-            self.config.speed = self.config.max_speed;
+        self.config.speed += acceleration * self.control.throttle_axis();
+
+        if self.config.speed > max_speed {
+            self.config.speed = max_speed;
         }
 
-        if self.config.speed < -self.config.max_speed / 2. {
-            self.config.speed = -self.config.max_speed / 2.;
+        if self.config.speed < -max_speed / 2. {
+            self.config.speed = -max_speed / 2.;
         }
 
         if self.config.speed > 0. {
-            self.config.speed -= self.config.friction;
+            self.config.speed -= friction;
         }
 
         if self.config.speed < 0. {
-            self.config.speed += self.config.friction;
+            self.config.speed += friction;
         }
 
-        if self.config.speed.abs() < self.config.friction {
+        if self.config.speed.abs() < friction {
             self.config.speed = 0.;
         }
 
+        // Turning swings the car's nose but not its momentum; whatever no longer
+        // lines up with the new heading becomes sideways slide.
+        let velocity = self.velocity();
         if self.config.speed != 0. {
             let flip = if self.config.speed > 0. { 1. } else { -1. };
-            if self.control.left {
-                self.config.angle += self.config.angle_delta * flip;
-            }
-            if self.control.right {
-                self.config.angle -= self.config.angle_delta * flip;
-            }
+            self.config.angle -=
+                self.config.angle_delta * steering * dt * flip * self.control.steering_axis();
+        }
+        self.config.angle += self.config.angular_speed * dt;
+        self.config.angular_speed *= (-self.config.angular_damping * dt).exp();
+        if self.config.angular_speed.abs() < MIN_ANGULAR_SPEED {
+            self.config.angular_speed = 0.;
         }
         self.config.angle = self.config.angle.rem_euclid(2. * std::f64::consts::PI);
-        self.x -= self.config.angle.sin() * self.config.speed;
-        self.y -= self.config.angle.cos() * self.config.speed;
+        self.set_velocity(velocity);
+
+        // Only grip stops a slide, so a car on ice keeps sliding whatever its friction.
+        self.config.lateral_speed *= (-grip * dt).exp();
+        if self.config.lateral_speed.abs() < MIN_LATERAL_SPEED {
+            self.config.lateral_speed = 0.;
+        }
+
+        let velocity = self.velocity();
+        self.x += velocity.x * dt;
+        self.y += velocity.y * dt;
+    }
+
+    /// Advance the car by a single frame of [`FRAME_DT`] seconds, the way `update`
+    /// behaved before it took a timestep.
+    pub fn update_frame(&mut self) {
+        self.update(FRAME_DT)
+    }
+
+    /// Feed `elapsed` seconds of wall-clock time into `timestep` and run as many
+    /// fixed-size updates as it hands out. Returns the number of updates run.
+    pub fn advance(&mut self, timestep: &mut FixedTimestep, elapsed: f64) -> usize {
+        let steps = timestep.advance(elapsed);
+        for _ in 0..steps {
+            self.update(timestep.dt);
+        }
+        steps
     }
 
     pub fn collides(&self, car: &Self) -> bool {
-        let self_hitbox: Rectangle = self.into();
-        let car_hitbox: Rectangle = car.into();
-        self_hitbox.intersects(&car_hitbox)
+        self.overlaps(car)
     }
 
     pub fn collides_car_view(&self, car_view: &CarView) -> bool {
-        let self_hitbox: Rectangle = self.into();
-        let car_hitbox: Rectangle = car_view.into();
-        self_hitbox.intersects(&car_hitbox)
+        self.overlaps(car_view)
     }
 
+    /// Whether this car overlaps a car at `car_position`, whose `angle` is read
+    /// the same way as this car's.
     pub fn collides_position(&self, car_position: &CarPosition) -> bool {
-        let self_hitbox: Rectangle = self.into();
-        let car_hitbox: Rectangle = Rectangle::new(
-            car_position.x,
-            car_position.y,
-            car_position.width,
-            car_position.height,
-            car_position.angle,
+        self.overlaps(car_position)
+    }
+
+    /// How this car's hitbox overlaps `car`'s, with the normal pointing towards `car`.
+    pub fn contact(&self, car: &Self) -> Option<Contact> {
+        self.contact_with(car)
+    }
+
+    /// Unit vector the car drives along when its speed is positive.
+    pub fn heading(&self) -> Corner {
+        Corner {
+            x: -self.config.angle.sin(),
+            y: -self.config.angle.cos(),
+        }
+    }
+
+    /// Unit vector pointing out of the car's right-hand side.
+    pub fn right(&self) -> Corner {
+        Corner {
+            x: self.config.angle.cos(),
+            y: -self.config.angle.sin(),
+        }
+    }
+
+    /// The car's velocity in world coordinates.
+    pub fn velocity(&self) -> Corner {
+        self.heading()
+            .scale(self.config.speed)
+            .add(&self.right().scale(self.config.lateral_speed))
+    }
+
+    /// Moment of inertia of the car about its centre, treating it as a solid rectangle.
+    pub fn moment_of_inertia(&self) -> f64 {
+        self.config.mass * (self.width * self.width + self.height * self.height) / 12.
+    }
+
+    pub fn inverse_moment_of_inertia(&self) -> f64 {
+        let inertia = self.moment_of_inertia();
+        if inertia > 0. && inertia.is_finite() {
+            1. / inertia
+        } else {
+            0.
+        }
+    }
+
+    /// World velocity of the point of the car that is `offset` away from its centre,
+    /// including the contribution of its spin.
+    pub fn velocity_at(&self, offset: &Corner) -> Corner {
+        // `angle` grows clockwise on screen, the opposite sense to `Corner::cross`.
+        let spin = -self.config.angular_speed;
+        self.velocity().add(&Corner {
+            x: -spin * offset.y,
+            y: spin * offset.x,
+        })
+    }
+
+    /// Set the car's velocity in world coordinates, splitting it into speed along
+    /// and across its heading.
+    pub fn set_velocity(&mut self, velocity: Corner) {
+        self.config.speed = velocity.dot(&self.heading());
+        self.config.lateral_speed = velocity.dot(&self.right());
+    }
+
+    /// If `self` and `car` overlap, push them apart and apply an impulse at the
+    /// contact point according to their masses and restitution. Hits away from a
+    /// car's centre set it spinning. Returns whether they were in contact.
+    pub fn resolve_collision(&mut self, car: &mut Car) -> bool {
+        self.collide_with(car).is_some()
+    }
+
+    /// Like [`Car::resolve_collision`], but describes the impact, as seen from `self`.
+    pub fn collide_with(&mut self, car: &mut Car) -> Option<Impact> {
+        let contact = self.contact(car)?;
+        let (normal, depth) = (contact.normal, contact.depth);
+        let point = contact.point();
+
+        let (inverse_mass_a, inverse_mass_b) =
+            (self.config.inverse_mass(), car.config.inverse_mass());
+        let total_inverse_mass = inverse_mass_a + inverse_mass_b;
+
+        let offset_a = point.sub(&Corner {
+            x: self.x,
+            y: self.y,
+        });
+        let offset_b = point.sub(&Corner { x: car.x, y: car.y });
+        let relative_velocity = car.velocity_at(&offset_b).sub(&self.velocity_at(&offset_a));
+        let mut impact = Impact {
+            normal,
+            point,
+            impulse: 0.,
+            relative_velocity,
+        };
+        if total_inverse_mass == 0. {
+            return Some(impact);
+        }
+
+        // Move each car out of the overlap in proportion to how light it is.
+        let share_a = depth * inverse_mass_a / total_inverse_mass;
+        let share_b = depth * inverse_mass_b / total_inverse_mass;
+        self.x -= normal.x * share_a;
+        self.y -= normal.y * share_a;
+        car.x += normal.x * share_b;
+        car.y += normal.y * share_b;
+
+        // Already moving apart.
+        let closing_speed = relative_velocity.dot(&normal);
+        if closing_speed >= 0. {
+            return Some(impact);
+        }
+
+        let (inverse_inertia_a, inverse_inertia_b) = (
+            self.inverse_moment_of_inertia(),
+            car.inverse_moment_of_inertia(),
+        );
+        let (lever_a, lever_b) = (offset_a.cross(&normal), offset_b.cross(&normal));
+        let restitution = (self.config.restitution + car.config.restitution) / 2.;
+        impact.impulse = -(1. + restitution) * closing_speed
+            / (total_inverse_mass
+                + lever_a * lever_a * inverse_inertia_a
+                + lever_b * lever_b * inverse_inertia_b);
+
+        self.apply_impulse(normal.scale(-impact.impulse), point);
+        car.apply_impulse(normal.scale(impact.impulse), point);
+        Some(impact)
+    }
+
+    /// Bounce off something immovable, like a wall. `contact` must have its normal
+    /// pointing from the obstacle towards the car. Returns whether the car was
+    /// moving into the obstacle.
+    pub fn bounce_off(&mut self, contact: &Contact, restitution: f64) -> bool {
+        self.impact_static(contact, restitution).impulse > 0.
+    }
+
+    /// Like [`Car::bounce_off`], but describes the impact, as seen from the car.
+    pub fn impact_static(&mut self, contact: &Contact, restitution: f64) -> Impact {
+        let (normal, point) = (contact.normal, contact.point());
+        self.x += normal.x * contact.depth;
+        self.y += normal.y * contact.depth;
+
+        let offset = point.sub(&Corner {
+            x: self.x,
+            y: self.y,
+        });
+        let velocity = self.velocity_at(&offset);
+        let mut impact = Impact {
+            normal: normal.scale(-1.),
+            point,
+            impulse: 0.,
+            relative_velocity: velocity.scale(-1.),
+        };
+        let closing_speed = velocity.dot(&normal);
+        if closing_speed >= 0. {
+            return impact;
+        }
+
+        let lever = offset.cross(&normal);
+        let restitution = (self.config.restitution + restitution) / 2.;
+        let impulse = -(1. + restitution) * closing_speed
+            / (self.config.inverse_mass() + lever * lever * self.inverse_moment_of_inertia());
+        if impulse.is_finite() {
+            self.apply_impulse(normal.scale(impulse), point);
+            impact.impulse = impulse;
+        }
+        impact
+    }
+
+    /// Which side of the car a world-space `point` is closest to, relative to the
+    /// car's size.
+    pub fn side_of(&self, point: &Corner) -> Side {
+        let offset = point.sub(&Corner {
+            x: self.x,
+            y: self.y,
+        });
+        let along = offset.dot(&self.heading()) / (self.height / 2.);
+        let across = offset.dot(&self.right()) / (self.width / 2.);
+        match (along.abs() >= across.abs(), along >= 0., across >= 0.) {
+            (true, true, _) => Side::Front,
+            (true, false, _) => Side::Rear,
+            (false, _, true) => Side::Right,
+            (false, _, false) => Side::Left,
+        }
+    }
+
+    /// Apply `impulse` at the world-space `point`, changing both the car's
+    /// velocity and, if `point` is off-centre, its spin.
+    pub fn apply_impulse(&mut self, impulse: Corner, point: Corner) {
+        let offset = point.sub(&Corner {
+            x: self.x,
+            y: self.y,
+        });
+        self.set_velocity(
+            self.velocity()
+                .add(&impulse.scale(self.config.inverse_mass())),
         );
-        self_hitbox.intersects(&car_hitbox)
+        // Torques come out in the on-screen sense, so flip them back into `angle`'s.
+        self.config.angular_speed -= offset.cross(&impulse) * self.inverse_moment_of_inertia();
     }
 }
 
+/// The hitbox of a car centred at `(x, y)` and facing `angle`. A [`Rectangle`]
+/// turns clockwise on screen with its angle while a car turns anticlockwise, so
+/// the hitbox is rotated by `-angle`, just as the canvas draws it.
+fn hitbox(x: f64, y: f64, width: f64, height: f64, angle: f64) -> Rectangle {
+    Rectangle::new(
+        x,
+        y,
+        width,
+        height,
+        (-angle).rem_euclid(std::f64::consts::TAU),
+    )
+}
+
 impl From<&Car> for Rectangle {
     fn from(car: &Car) -> Self {
-        Rectangle {
-            x: car.x,
-            y: car.y,
-            width: car.width,
-            height: car.height,
-            angle: car.config.angle.rem_euclid(std::f64::consts::TAU),
-        }
+        hitbox(car.x, car.y, car.width, car.height, car.config.angle)
     }
 }
 
 impl From<&CarView> for Rectangle {
     fn from(car_view: &CarView) -> Self {
-        Rectangle {
-            x: car_view.x,
-            y: car_view.y,
-            width: car_view.width,
-            height: car_view.height,
-            angle: car_view.config.angle.rem_euclid(std::f64::consts::TAU),
-        }
+        hitbox(
+            car_view.x,
+            car_view.y,
+            car_view.width,
+            car_view.height,
+            car_view.config.angle,
+        )
     }
 }
 
+impl From<&CarPosition> for Rectangle {
+    fn from(car_position: &CarPosition) -> Self {
+        hitbox(
+            car_position.x,
+            car_position.y,
+            car_position.width,
+            car_position.height,
+            car_position.angle,
+        )
+    }
+}
+
+impl Shape for Car {
+    fn collider(&self) -> Collider {
+        Rectangle::from(self).collider()
+    }
+
+    fn aabb(&self) -> Aabb {
+        Rectangle::from(self).aabb()
+    }
+}
+
+impl Shape for CarView {
+    fn collider(&self) -> Collider {
+        Rectangle::from(self).collider()
+    }
+
+    fn aabb(&self) -> Aabb {
+        Rectangle::from(self).aabb()
+    }
+}
+
+impl Shape for CarPosition {
+    fn collider(&self) -> Collider {
+        Rectangle::from(self).collider()
+    }
+
+    fn aabb(&self) -> Aabb {
+        Rectangle::from(self).aabb()
+    }
+}
+
+/// A side of a car's body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Side {
+    Front,
+    Rear,
+    Left,
+    Right,
+}
+
+/// What happened when a car hit something.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Impact {
+    /// Unit normal pointing from the car towards what it hit.
+    pub normal: Corner,
+    /// Where they touched, in world coordinates.
+    pub point: Corner,
+    /// Magnitude of the impulse that pushed them apart. Zero if they were
+    /// touching but already separating.
+    pub impulse: f64,
+    /// Velocity of what was hit relative to the car, at the contact point and
+    /// just before the impulse.
+    pub relative_velocity: Corner,
+}
+
+/// What the driver is asking the car to do.
+///
+/// Digital inputs (keys) set the booleans and analog ones (gamepads, touch
+/// joysticks, AI drivers) set `throttle` and `steering`. Both may be used at once;
+/// see [`Control::throttle_axis`] and [`Control::steering_axis`] for how they combine.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
 pub struct Control {
     pub forward: bool,
     pub reverse: bool,
     pub left: bool,
     pub right: bool,
+    /// Analog throttle in `[-1, 1]`. Positive accelerates forwards, negative
+    /// brakes and then reverses.
+    #[serde(default)]
+    pub throttle: f64,
+    /// Analog steering in `[-1, 1]`. Positive turns right, negative turns left.
+    #[serde(default)]
+    pub steering: f64,
+    /// Cut the whole car's grip and turn harder, for a tighter, sliding turn. See
+    /// [`CarConfig::handbrake_grip`].
+    #[serde(default)]
+    pub handbrake: bool,
+    /// Spend the boost meter for more acceleration and top speed.
+    #[serde(default)]
+    pub boost: bool,
+}
+
+impl Control {
+    /// Analog-only inputs, with every boolean released.
+    pub fn analog(throttle: f64, steering: f64) -> Self {
+        Control {
+            throttle,
+            steering,
+            ..Default::default()
+        }
+    }
+
+    /// The throttle actually applied, in `[-1, 1]`: `forward` and `reverse` count
+    /// as full throttle either way on top of the analog `throttle`.
+    pub fn throttle_axis(&self) -> f64 {
+        axis(self.throttle, self.forward, self.reverse)
+    }
+
+    /// The steering actually applied, in `[-1, 1]`: `right` and `left` count as
+    /// full lock either way on top of the analog `steering`.
+    pub fn steering_axis(&self) -> f64 {
+        axis(self.steering, self.right, self.left)
+    }
+}
+
+/// Combine an analog axis with a pair of digital inputs, clamped to `[-1, 1]`.
+/// NaN analog input counts as centred.
+fn axis(analog: f64, positive: bool, negative: bool) -> f64 {
+    let analog = if analog.is_nan() { 0. } else { analog };
+    (analog + positive as u8 as f64 - negative as u8 as f64).clamp(-1., 1.)
 }
 
 #[cfg(test)]
 pub mod tests {
-    use super::Car;
+    use super::{Car, CarConfig, CarConfigError, CarPosition, Control, Corner, Side, FRAME_DT};
 
     #[test]
     fn test_collides() {
         let car1 = Car::new(100., 100., 60., 80.).with_angle(-9.3);
-        let car2 = Car::new(100., 100., 60., 80.).with_angle(-3.14);
+        let car2 = Car::new(100., 100., 60., 80.).with_angle(-std::f64::consts::PI);
         assert!(car1.collides(&car2));
     }
+
+    #[test]
+    fn test_rotated_hitbox_points_where_the_car_heads() {
+        // A long, thin car turned to the left: its nose is up and to the left.
+        let car = Car::new(100., 100., 20., 200.).with_angle(0.5);
+        let nose = car.center().add(&car.heading().scale(90.));
+        let mirrored = Corner {
+            x: 2. * car.x - nose.x,
+            y: nose.y,
+        };
+        let probe = |at: &Corner| Car::new(at.x, at.y, 10., 10.);
+        assert!(car.collides(&probe(&nose)));
+        assert!(!car.collides(&probe(&mirrored)));
+
+        let position = |at: &Corner| CarPosition {
+            x: at.x,
+            y: at.y,
+            width: 20.,
+            height: 200.,
+            angle: 0.5,
+        };
+        assert!(probe(&nose).collides_position(&position(&car.center())));
+        assert!(!probe(&mirrored).collides_position(&position(&car.center())));
+    }
+
+    #[test]
+    fn test_update_frame_matches_per_frame_tuning() {
+        let mut car = Car::new(100., 100., 60., 80.);
+        car.control.forward = true;
+        car.update_frame();
+        // One frame of acceleration (0.2) minus one frame of friction (0.05).
+        assert!((car.config.speed * FRAME_DT - 0.15).abs() < 1e-9);
+        assert!((car.y - (100. - 0.15)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_update_is_frame_rate_independent() {
+        let mut slow = Car::new(100., 100., 60., 80.);
+        let mut fast = slow.clone();
+        slow.control.forward = true;
+        fast.control.forward = true;
+
+        for _ in 0..60 {
+            slow.update(1. / 60.);
+        }
+        for _ in 0..144 {
+            fast.update(1. / 144.);
+        }
+        assert!((slow.config.speed - fast.config.speed).abs() < 1e-6);
+        assert!((slow.y - fast.y).abs() / (100. - slow.y) < 0.02);
+    }
+
+    #[test]
+    fn test_rammed_car_gets_knocked_away() {
+        // `rammer` drives up (towards -y) into `target`, which sits still.
+        let mut rammer = Car::new(100., 100., 60., 80.);
+        let mut target = Car::new(100., 30., 60., 80.);
+        rammer.config.speed = 300.;
+
+        assert!(rammer.resolve_collision(&mut target));
+        assert!(target.config.speed > 0.);
+        assert!(rammer.config.speed < 300.);
+        assert!(target.y < 30.);
+
+        // Momentum is conserved between equal masses.
+        assert!((rammer.config.speed + target.config.speed - 300.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_side_hit_pushes_car_sideways_then_grip_recovers() {
+        // `rammer` drives left (towards -x) into the right-hand side of `target`.
+        let mut rammer = Car::new(160., 100., 60., 80.).with_angle(std::f64::consts::FRAC_PI_2);
+        let mut target = Car::new(100., 100., 60., 80.);
+        rammer.config.speed = 300.;
+
+        assert!(rammer.resolve_collision(&mut target));
+        assert!(target.config.speed.abs() < 1e-9);
+        assert!(target.config.lateral_speed < 0.);
+
+        let x = target.x;
+        target.update(0.1);
+        assert!(target.x < x);
+
+        for _ in 0..60 {
+            target.update(FRAME_DT);
+        }
+        assert_eq!(target.config.lateral_speed, 0.);
+
+        // Without grip, nothing but walls and cars stops a slide.
+        target.config.grip = 0.;
+        target.config.lateral_speed = -20.;
+        for _ in 0..60 {
+            target.update(FRAME_DT);
+        }
+        assert_eq!(target.config.lateral_speed, -20.);
+    }
+
+    #[test]
+    fn test_off_centre_hit_spins_car() {
+        // `rammer` clips the right-hand half of `target`'s rear bumper.
+        let mut rammer = Car::new(130., 178., 60., 80.);
+        let mut target = Car::new(100., 100., 60., 80.);
+        rammer.config.speed = 300.;
+
+        assert!(rammer.resolve_collision(&mut target));
+        // Pushed forward from behind the right side, the nose swings left,
+        // which is the direction `angle` grows in. The rammer's left front is
+        // shoved back, so it turns left too.
+        assert!(target.config.angular_speed > 0.);
+        assert!(rammer.config.angular_speed > 0.);
+
+        let angle = target.config.angle;
+        target.update(FRAME_DT);
+        assert!(target.config.angle > angle);
+
+        for _ in 0..600 {
+            target.update(FRAME_DT);
+        }
+        assert_eq!(target.config.angular_speed, 0.);
+    }
+
+    #[test]
+    fn test_analog_control_scales_acceleration_and_steering() {
+        let drive = |control: Control| {
+            let mut car = Car::new(100., 100., 60., 80.);
+            car.config.speed = 100.;
+            car.control = control;
+            car.update(FRAME_DT);
+            (car.config.speed, car.config.angle)
+        };
+        let (coast_speed, _) = drive(Control::default());
+        let (full_speed, _) = drive(Control {
+            forward: true,
+            ..Default::default()
+        });
+        let (half_speed, _) = drive(Control::analog(0.5, 0.));
+        let (_, full_angle) = drive(Control {
+            right: true,
+            ..Default::default()
+        });
+        let (_, half_angle) = drive(Control::analog(0., 0.5));
+
+        assert!((half_speed - coast_speed - (full_speed - coast_speed) / 2.).abs() < 1e-9);
+        let full_turn = full_angle - 2. * std::f64::consts::PI;
+        let half_turn = half_angle - 2. * std::f64::consts::PI;
+        assert!(full_turn < 0.);
+        assert!((half_turn - full_turn / 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_control_axes_combine_and_clamp() {
+        let control = Control {
+            forward: true,
+            left: true,
+            throttle: 0.5,
+            steering: 0.25,
+            ..Default::default()
+        };
+        assert_eq!(control.throttle_axis(), 1.);
+        assert_eq!(control.steering_axis(), -0.75);
+        assert_eq!(Control::analog(f64::NAN, -3.).throttle_axis(), 0.);
+        assert_eq!(Control::analog(f64::NAN, -3.).steering_axis(), -1.);
+    }
+
+    #[test]
+    fn test_boost_raises_top_speed_until_the_meter_runs_out() {
+        let mut car = Car::new(100., 100., 60., 80.);
+        car.config.speed = car.config.max_speed;
+        car.control = Control {
+            forward: true,
+            boost: true,
+            ..Default::default()
+        };
+        let mut top_speed: f64 = 0.;
+        let mut frames = 0;
+        while car.boost > 0. {
+            car.update(FRAME_DT);
+            top_speed = top_speed.max(car.config.speed);
+            frames += 1;
+        }
+        assert!(top_speed > car.config.max_speed);
+        assert!((frames as f64 * FRAME_DT - car.config.boost_capacity).abs() <= FRAME_DT);
+
+        car.update(FRAME_DT);
+        assert!(car.config.speed <= car.config.max_speed);
+
+        car.control.boost = false;
+        car.boost = 0.;
+        car.update(1.);
+        assert!((car.boost - car.config.boost_recharge).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_handbrake_turns_tighter_and_slides_further() {
+        let corner = |handbrake: bool| {
+            let mut car = Car::new(100., 100., 60., 80.);
+            car.config.speed = car.config.max_speed;
+            car.control = Control {
+                right: true,
+                handbrake,
+                ..Default::default()
+            };
+            for _ in 0..10 {
+                car.update(FRAME_DT);
+            }
+            (car.config.angle, car.config.lateral_speed.abs())
+        };
+        let (gripping_angle, gripping_slide) = corner(false);
+        let (sliding_angle, sliding_slide) = corner(true);
+        assert!(sliding_angle < gripping_angle);
+        assert!(sliding_slide > gripping_slide);
+    }
+
+    #[test]
+    fn test_builder_validates_ranges() {
+        let config = CarConfig::builder()
+            .max_speed(300.)
+            .mass(2.)
+            .boost_capacity(1.)
+            .build()
+            .unwrap();
+        assert_eq!(config.max_speed, 300.);
+        assert_eq!(config.boost_capacity, 1.);
+        let car = Car::new(0., 0., 60., 80.).with_config(config);
+        assert_eq!(car.boost, 1.);
+
+        assert_eq!(
+            CarConfig::builder().friction(-1.).build().unwrap_err(),
+            CarConfigError::TooSmall {
+                field: "friction",
+                min: 0.
+            }
+        );
+        assert_eq!(
+            CarConfig::builder().max_speed(0.).build().unwrap_err(),
+            CarConfigError::NotPositive { field: "max_speed" }
+        );
+        assert_eq!(
+            CarConfig::builder().angle(f64::NAN).build().unwrap_err(),
+            CarConfigError::NotFinite { field: "angle" }
+        );
+        assert_eq!(
+            CarConfig::builder()
+                .restitution(1.5)
+                .build()
+                .unwrap_err()
+                .field(),
+            "restitution"
+        );
+        assert!(CarConfig::builder().mass(f64::INFINITY).build().is_ok());
+        assert!(CarConfig::default().validate().is_ok());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let car = Car::new(100., 100., 60., 80.).with_angle(1.);
+        let json = car.json().unwrap();
+        assert_eq!(Car::from_json(&json).unwrap().config.angle, 1.);
+        assert!(Car::from_json("{\"x\": 1}").is_err());
+    }
+
+    #[test]
+    fn test_side_of() {
+        let car = Car::new(100., 100., 60., 80.);
+        assert_eq!(car.side_of(&Corner { x: 100., y: 60. }), Side::Front);
+        assert_eq!(car.side_of(&Corner { x: 100., y: 140. }), Side::Rear);
+        assert_eq!(car.side_of(&Corner { x: 130., y: 100. }), Side::Right);
+        assert_eq!(car.side_of(&Corner { x: 70., y: 110. }), Side::Left);
+    }
+
+    #[test]
+    fn test_centred_hit_does_not_spin() {
+        let mut rammer = Car::new(100., 178., 60., 80.);
+        let mut target = Car::new(100., 100., 60., 80.);
+        rammer.config.speed = 300.;
+
+        assert!(rammer.resolve_collision(&mut target));
+        assert!(target.config.angular_speed.abs() < 1e-9);
+        assert!(rammer.config.angular_speed.abs() < 1e-9);
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -218,6 +1168,29 @@ pub struct CarView {
     pub right: bool,
     pub forward: bool,
     pub reverse: bool,
+    #[serde(default)]
+    pub throttle: f64,
+    #[serde(default)]
+    pub steering: f64,
+    #[serde(default)]
+    pub handbrake: bool,
+    #[serde(default)]
+    pub boost: bool,
+}
+
+impl CarView {
+    pub fn control(&self) -> Control {
+        Control {
+            forward: self.forward,
+            reverse: self.reverse,
+            left: self.left,
+            right: self.right,
+            throttle: self.throttle,
+            steering: self.steering,
+            handbrake: self.handbrake,
+            boost: self.boost,
+        }
+    }
 }
 
 impl From<CarView> for Car {
@@ -229,12 +1202,9 @@ impl From<CarView> for Car {
             config: car_view.config,
             height: car_view.height,
             width: car_view.width,
-            control: Control {
-                forward: car_view.forward,
-                reverse: car_view.reverse,
-                left: car_view.left,
-                right: car_view.right,
-            },
+            control: car_view.control(),
+            boost: car_view.config.boost_capacity,
+            lidar: None,
         }
     }
 }
@@ -248,12 +1218,9 @@ impl From<&CarView> for Car {
             config: car_view.config,
             height: car_view.height,
             width: car_view.width,
-            control: Control {
-                forward: car_view.forward,
-                reverse: car_view.reverse,
-                left: car_view.left,
-                right: car_view.right,
-            },
+            control: car_view.control(),
+            boost: car_view.config.boost_capacity,
+            lidar: None,
         }
     }
 }
@@ -0,0 +1,205 @@
+use crate::{Car, ConvexPolygon, Corner, Rectangle, Shape, Surface};
+
+/// How many times to halve the search interval when looking for the time of impact.
+const TIME_OF_IMPACT_ITERATIONS: usize = 24;
+
+/// The convex hull of `points`, by Andrew's monotone chain.
+pub fn convex_hull(mut points: Vec<Corner>) -> ConvexPolygon {
+    points.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    points.dedup_by(|a, b| a.sub(b).length() < 1e-12);
+    if points.len() < 3 {
+        return ConvexPolygon::new(points);
+    }
+
+    let turns_left = |hull: &[Corner], point: &Corner| {
+        let (a, b) = (hull[hull.len() - 2], hull[hull.len() - 1]);
+        b.sub(&a).cross(&point.sub(&b)) > 0.
+    };
+    let mut lower: Vec<Corner> = Vec::new();
+    for point in &points {
+        while lower.len() >= 2 && !turns_left(&lower, point) {
+            lower.pop();
+        }
+        lower.push(*point);
+    }
+    let mut upper: Vec<Corner> = Vec::new();
+    for point in points.iter().rev() {
+        while upper.len() >= 2 && !turns_left(&upper, point) {
+            upper.pop();
+        }
+        upper.push(*point);
+    }
+    lower.pop();
+    upper.pop();
+    lower.extend(upper);
+    ConvexPolygon::new(lower)
+}
+
+/// The pose of a car `t` of the way, in `[0, 1]`, from `start` to `end`.
+pub(crate) fn interpolate(start: &Car, end: &Car, t: f64) -> Car {
+    let turn = (end.config.angle - start.config.angle + std::f64::consts::PI)
+        .rem_euclid(std::f64::consts::TAU)
+        - std::f64::consts::PI;
+    let mut car = end.clone();
+    car.x = start.x + (end.x - start.x) * t;
+    car.y = start.y + (end.y - start.y) * t;
+    car.config.angle = start.config.angle + turn * t;
+    car
+}
+
+/// Everything a car's hitbox passes over while moving from `start` to `end`.
+/// Exact for straight-line motion, and a close fit for the small turns made in
+/// a single step.
+pub fn swept_hitbox(start: &Car, end: &Car) -> ConvexPolygon {
+    let mut points = Rectangle::from(start).vertices();
+    points.extend(Rectangle::from(end).vertices());
+    convex_hull(points)
+}
+
+/// The fraction of the motion from `start` to `end`, in `[0, 1]`, at which the car
+/// first touches `obstacle`. `None` if it never does, or if it already overlaps
+/// `obstacle` at `start` (which is for the regular collision response to sort out).
+pub fn time_of_impact<S: Shape + ?Sized>(start: &Car, end: &Car, obstacle: &S) -> Option<f64> {
+    if start.overlaps(obstacle) {
+        return None;
+    }
+    let touches_by = |t: f64| {
+        let hull = swept_hitbox(start, &interpolate(start, end, t));
+        hull.aabb().overlaps(&obstacle.aabb()) && hull.overlaps(obstacle)
+    };
+    if !touches_by(1.) {
+        return None;
+    }
+
+    // Bisect for the earliest moment the swept hitbox reaches the obstacle, and
+    // report the end of the interval so the car is left just touching it.
+    let (mut clear, mut touching) = (0., 1.);
+    for _ in 0..TIME_OF_IMPACT_ITERATIONS {
+        let middle = (clear + touching) / 2.;
+        if touches_by(middle) {
+            touching = middle;
+        } else {
+            clear = middle;
+        }
+    }
+    Some(touching)
+}
+
+/// Like [`time_of_impact`], but with both cars on the move: `a` from `start_a` to
+/// `end_a` and `b` from `start_b` to `end_b`. Worked out from `b`'s point of view,
+/// so how far `b` turns over the motion is ignored.
+pub fn mutual_time_of_impact(
+    start_a: &Car,
+    end_a: &Car,
+    start_b: &Car,
+    end_b: &Car,
+) -> Option<f64> {
+    let mut relative = end_a.clone();
+    relative.x -= end_b.x - start_b.x;
+    relative.y -= end_b.y - start_b.y;
+    time_of_impact(start_a, &relative, start_b)
+}
+
+/// Whether two cars moved far enough, relative to each other, that one could have
+/// passed clean through the other between `start` and `end` without the two ever
+/// overlapping at either end.
+pub fn could_tunnel(start_a: &Car, end_a: &Car, start_b: &Car, end_b: &Car) -> bool {
+    let moved_a = end_a.center().sub(&start_a.center());
+    let moved_b = end_b.center().sub(&start_b.center());
+    let thinnest = [start_a.width, start_a.height, start_b.width, start_b.height]
+        .into_iter()
+        .fold(f64::INFINITY, f64::min);
+    moved_a.sub(&moved_b).length() > thinnest
+}
+
+impl Car {
+    /// Advance the car by `dt` seconds like [`Car::update`], but if that would carry
+    /// it into or straight through one of `obstacles`, stop it where it first touches.
+    /// Returns the index of the obstacle it was stopped by.
+    pub fn update_swept<S: Shape>(&mut self, dt: f64, obstacles: &[S]) -> Option<usize> {
+        self.update_swept_on(dt, &Surface::default(), obstacles)
+    }
+
+    /// Like [`Car::update_swept`], but driving on `surface`.
+    pub fn update_swept_on<S: Shape>(
+        &mut self,
+        dt: f64,
+        surface: &Surface,
+        obstacles: &[S],
+    ) -> Option<usize> {
+        let start = self.clone();
+        self.update_on(dt, surface);
+
+        let reach = swept_hitbox(&start, self).aabb();
+        let (index, t) = obstacles
+            .iter()
+            .enumerate()
+            .filter(|(_, obstacle)| obstacle.aabb().overlaps(&reach))
+            .filter_map(|(index, obstacle)| Some((index, time_of_impact(&start, self, obstacle)?)))
+            .min_by(|(_, t1), (_, t2)| t1.total_cmp(t2))?;
+
+        let stopped = interpolate(&start, self, t);
+        self.x = stopped.x;
+        self.y = stopped.y;
+        self.config.angle = stopped.config.angle;
+        Some(index)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Arena, Wall, FRAME_DT};
+
+    fn thin_wall() -> Wall {
+        // A 2px thick wall across the car's path, facing down towards it.
+        Wall::new(Corner { x: 0., y: 500. }, Corner { x: 1000., y: 500. }, 2.)
+    }
+
+    fn fast_car() -> Car {
+        let mut car = Car::new(500., 600., 20., 20.);
+        car.config.max_speed = 30_000.;
+        car.config.speed = 30_000.;
+        car
+    }
+
+    #[test]
+    fn test_fast_car_tunnels_without_ccd() {
+        let arena = Arena::new(1000., 1000.).with_wall(thin_wall());
+        let mut car = fast_car();
+        car.update(FRAME_DT);
+        arena.collide(&mut car);
+        assert!(car.y < 490.);
+    }
+
+    #[test]
+    fn test_fast_car_stops_at_thin_wall() {
+        let mut car = fast_car();
+        let hit = car.update_swept(FRAME_DT, &[thin_wall()]);
+        assert_eq!(hit, Some(0));
+        assert!((car.y - 510.).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_time_of_impact_against_car() {
+        let start = Car::new(0., 0., 20., 20.);
+        let mut end = start.clone();
+        end.y = -200.;
+        let parked = Car::new(0., -100., 20., 20.);
+
+        // Contact once the gap of 80px between them closes, 40% of the way along.
+        let t = time_of_impact(&start, &end, &parked).unwrap();
+        assert!((t - 0.4).abs() < 1e-6);
+
+        let beside = Car::new(100., -100., 20., 20.);
+        assert!(time_of_impact(&start, &end, &beside).is_none());
+
+        // Closing the same gap from both ends meets in the middle.
+        let mut parked_end = parked.clone();
+        parked_end.y = 100.;
+        assert!(could_tunnel(&start, &end, &parked, &parked_end));
+        let t = mutual_time_of_impact(&start, &end, &parked, &parked_end).unwrap();
+        assert!((t - 0.2).abs() < 1e-6);
+        assert!(!could_tunnel(&start, &start, &parked, &parked));
+    }
+}
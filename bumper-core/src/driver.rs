@@ -0,0 +1,350 @@
+use crate::{Car, CarId, Control, Lidar, World};
+
+/// What a [`Driver`] gets to see before it picks its inputs: the whole world, and
+/// which car in it is its own.
+#[derive(Debug, Clone, Copy)]
+pub struct Observation<'a> {
+    pub id: CarId,
+    pub car: &'a Car,
+    pub world: &'a World,
+}
+
+impl<'a> Observation<'a> {
+    /// Every car but the driver's own.
+    pub fn others(&self) -> impl Iterator<Item = (CarId, &'a Car)> + '_ {
+        self.world.cars().filter(move |(id, _)| *id != self.id)
+    }
+}
+
+/// Anything that can drive a car, be it a person at a keyboard, a recording or a
+/// bot. Drivers are asked for their inputs once per [`World::step`], so people and
+/// bots can take each other's place without the simulation knowing.
+pub trait Driver {
+    fn drive(&mut self, observation: &Observation) -> Control;
+}
+
+impl<F: FnMut(&Observation) -> Control> Driver for F {
+    fn drive(&mut self, observation: &Observation) -> Control {
+        self(observation)
+    }
+}
+
+impl World {
+    /// What the driver of car `id` can see, or `None` if there is no such car.
+    pub fn observe(&self, id: CarId) -> Option<Observation<'_>> {
+        Some(Observation {
+            id,
+            car: self.car(id)?,
+            world: self,
+        })
+    }
+
+    /// Ask `driver` for car `id`'s inputs and apply them. Returns `false` if there
+    /// is no such car.
+    pub fn drive<D: Driver + ?Sized>(&mut self, id: CarId, driver: &mut D) -> bool {
+        let control = match self.observe(id) {
+            Some(observation) => driver.drive(&observation),
+            None => return false,
+        };
+        self.set_control(id, control)
+    }
+}
+
+/// A person at a keyboard. Feed it key presses as they happen; it drives with
+/// whatever is held down. Keys are named like the browser's `KeyboardEvent.key`:
+/// the arrow keys steer and accelerate, space is the handbrake and shift boosts.
+#[derive(Debug, Clone, Default)]
+pub struct Keyboard {
+    held: Control,
+}
+
+impl Keyboard {
+    /// Returns whether `key` means anything to the car.
+    pub fn key_down(&mut self, key: &str) -> bool {
+        self.set(key, true)
+    }
+
+    /// Returns whether `key` means anything to the car.
+    pub fn key_up(&mut self, key: &str) -> bool {
+        self.set(key, false)
+    }
+
+    pub fn control(&self) -> Control {
+        self.held
+    }
+
+    fn set(&mut self, key: &str, down: bool) -> bool {
+        let held = match key {
+            "ArrowUp" => &mut self.held.forward,
+            "ArrowDown" => &mut self.held.reverse,
+            "ArrowLeft" => &mut self.held.left,
+            "ArrowRight" => &mut self.held.right,
+            " " => &mut self.held.handbrake,
+            "Shift" => &mut self.held.boost,
+            _ => return false,
+        };
+        *held = down;
+        true
+    }
+}
+
+impl Driver for Keyboard {
+    fn drive(&mut self, _: &Observation) -> Control {
+        self.held
+    }
+}
+
+/// Plays back a fixed list of inputs, each held for a number of seconds, counted
+/// in simulated time from the first time it's asked to drive. Handy for tests,
+/// replays and demos.
+#[derive(Debug, Clone, Default)]
+pub struct Script {
+    /// How long, in seconds, to hold each set of inputs, in order.
+    pub steps: Vec<(f64, Control)>,
+    /// Start over once the last step is done, rather than letting go of everything.
+    pub looping: bool,
+    started: Option<f64>,
+}
+
+impl Script {
+    pub fn new(steps: Vec<(f64, Control)>) -> Self {
+        Script {
+            steps,
+            ..Default::default()
+        }
+    }
+
+    pub fn looping(self) -> Self {
+        Script {
+            looping: true,
+            ..self
+        }
+    }
+
+    /// The inputs held `elapsed` seconds into the script.
+    pub fn control_at(&self, elapsed: f64) -> Control {
+        let total = self.steps.iter().map(|(duration, _)| duration).sum::<f64>();
+        let mut elapsed = if self.looping && total > 0. {
+            elapsed.rem_euclid(total)
+        } else {
+            elapsed
+        };
+        for (duration, control) in &self.steps {
+            if elapsed < *duration {
+                return *control;
+            }
+            elapsed -= duration;
+        }
+        Control::default()
+    }
+}
+
+impl Driver for Script {
+    fn drive(&mut self, observation: &Observation) -> Control {
+        let started = *self.started.get_or_insert(observation.world.time);
+        self.control_at(observation.world.time - started)
+    }
+}
+
+/// The simplest self-driving car: it steers away from whatever its [`Lidar`] sees
+/// close by and towards the most open direction, slows down as the way ahead
+/// closes in and backs off when something is right in front. Uses the car's own
+/// sensors if it has any, otherwise `lidar`.
+#[derive(Debug, Clone)]
+pub struct Autopilot {
+    pub lidar: Lidar,
+    /// Fraction of the car's top speed to drive at when the way ahead is clear.
+    pub cruise: f64,
+    /// Back up when the way ahead is closer than this fraction of the sensor range.
+    pub caution: f64,
+    /// Only steer around things closer than this fraction of the sensor range.
+    pub clearance: f64,
+}
+
+impl Default for Autopilot {
+    fn default() -> Self {
+        Autopilot {
+            lidar: Lidar::default(),
+            cruise: 0.6,
+            caution: 0.25,
+            clearance: 0.5,
+        }
+    }
+}
+
+impl Autopilot {
+    /// Readings for the car being driven, taken fresh unless it carries its own.
+    pub fn readings(&self, observation: &Observation) -> Vec<f64> {
+        let (car, world) = (observation.car, observation.world);
+        match &car.lidar {
+            Some(lidar) if lidar.readings.len() == lidar.rays => lidar.readings.clone(),
+            lidar => {
+                let lidar = lidar.as_ref().unwrap_or(&self.lidar);
+                lidar.scan(car, |ray| {
+                    world
+                        .raycast(ray, lidar.range, Some(observation.id))
+                        .map(|(_, hit)| hit.distance)
+                })
+            }
+        }
+    }
+
+    /// Inputs that keep a car clear of what `readings` see, steering as asked while
+    /// nothing is within `clearance`.
+    pub fn avoid(&self, car: &Car, readings: &[f64], steering: f64) -> Control {
+        if readings.is_empty() {
+            return Control::analog(throttle_for(car, self.cruise), steering);
+        }
+
+        // Rays run from left to right, so the middle one looks straight ahead and
+        // the ones either side of it cover the front corners.
+        let middle = (readings.len() - 1) as f64 / 2.;
+        let side = |i: usize| {
+            if middle > 0. {
+                (middle - i as f64) / middle
+            } else {
+                0.
+            }
+        };
+        let ahead = readings
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| (*i as f64 - middle).abs() <= 1.)
+            .map(|(_, reading)| *reading)
+            .fold(1., f64::min);
+        // Something close on the left pushes right, and the other way round.
+        let push = readings
+            .iter()
+            .enumerate()
+            .map(|(i, reading)| side(i) * (1. - reading))
+            .sum::<f64>();
+        let (open, _) = readings
+            .iter()
+            .enumerate()
+            .max_by(|(i, a), (j, b)| {
+                a.total_cmp(b)
+                    // Of equally open directions, prefer the straightest.
+                    .then(side(*j).abs().total_cmp(&side(*i).abs()))
+            })
+            .expect("Checked above.");
+        let clear = readings.iter().all(|reading| *reading >= self.clearance);
+        let steering = if clear {
+            steering
+        } else {
+            (2. * push - side(open)).clamp(-1., 1.)
+        };
+
+        if ahead < self.caution {
+            // Reversing turns the other way, so swing the back towards the gap.
+            Control::analog(-1., -steering)
+        } else {
+            let room = (ahead - self.caution) / (1. - self.caution);
+            Control::analog(throttle_for(car, self.cruise * room), steering)
+        }
+    }
+}
+
+impl Driver for Autopilot {
+    fn drive(&mut self, observation: &Observation) -> Control {
+        let readings = self.readings(observation);
+        self.avoid(observation.car, &readings, 0.)
+    }
+}
+
+/// Throttle that brings `car` towards `fraction` of its top speed: full throttle
+/// when well below it, braking when well above and easing off in between.
+pub fn throttle_for(car: &Car, fraction: f64) -> f64 {
+    let target = car.config.max_speed * fraction;
+    // Within a tenth of a second's acceleration the throttle is proportional.
+    ((target - car.config.speed) / (car.config.acceleration * 0.1)).clamp(-1., 1.)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Arena, Corner, Struck, WorldEvent};
+
+    #[test]
+    fn test_keyboard_and_closure_drivers_are_interchangeable() {
+        let mut world = World::default();
+        let id = world.add_car(Car::new(500., 500., 60., 80.));
+
+        let mut keyboard = Keyboard::default();
+        assert!(keyboard.key_down("ArrowUp"));
+        assert!(keyboard.key_down("Shift"));
+        assert!(!keyboard.key_down("q"));
+        assert!(world.drive(id, &mut keyboard));
+        assert!(world.car(id).unwrap().control.forward);
+        assert!(world.car(id).unwrap().control.boost);
+        keyboard.key_up("ArrowUp");
+        assert!(!keyboard.control().forward);
+
+        let mut drivers: Vec<Box<dyn Driver>> = vec![
+            Box::new(keyboard),
+            Box::new(|_: &Observation| Control {
+                left: true,
+                ..Default::default()
+            }),
+        ];
+        world.drive(id, drivers[1].as_mut());
+        assert!(world.car(id).unwrap().control.left);
+        assert!(!world.drive(id + 1, drivers[0].as_mut()));
+    }
+
+    #[test]
+    fn test_script_plays_back_in_simulated_time() {
+        let forward = Control {
+            forward: true,
+            ..Default::default()
+        };
+        let left = Control {
+            left: true,
+            ..Default::default()
+        };
+        let mut world = World::default();
+        let id = world.add_car(Car::new(500., 500., 60., 80.));
+        let mut script = Script::new(vec![(0.5, forward), (0.5, left)]);
+
+        let mut seen = Vec::new();
+        for _ in 0..90 {
+            world.drive(id, &mut script);
+            seen.push(world.car(id).unwrap().control);
+            world.step();
+        }
+        // Steps are 1/60 s, give or take rounding at the boundaries.
+        assert!(seen[..29].iter().all(|control| control.forward));
+        assert!(seen[31..59].iter().all(|control| control.left));
+        assert!(seen[61..]
+            .iter()
+            .all(|control| !control.forward && !control.left));
+
+        let looping = Script::new(vec![(0.5, forward), (0.5, left)]).looping();
+        assert!(looping.control_at(1.25).forward);
+    }
+
+    #[test]
+    fn test_autopilot_keeps_off_the_walls() {
+        let mut world = World::new(Arena::new(1000., 1000.));
+        // Heading straight for the top wall.
+        let id = world.add_car(Car::new(500., 300., 60., 80.));
+        let mut autopilot = Autopilot::default();
+        for _ in 0..600 {
+            world.drive(id, &mut autopilot);
+            world.step();
+            for event in world.drain_events() {
+                if let WorldEvent::Collision(collision) = event {
+                    assert!(!matches!(collision.other, Struck::Wall { .. }));
+                }
+            }
+        }
+        assert!(
+            world
+                .car(id)
+                .unwrap()
+                .center()
+                .sub(&Corner { x: 500., y: 300. })
+                .length()
+                > 100.
+        );
+    }
+}
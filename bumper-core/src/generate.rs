@@ -0,0 +1,432 @@
+use crate::{
+    Corner, Map, MapError, Rectangle, Shape, Spawn, SplitMix64, DEFAULT_ARENA_HEIGHT,
+    DEFAULT_ARENA_WIDTH, SPAWN_CAR_SIZE,
+};
+
+/// How many random placements to try per obstacle before giving up on it.
+const ATTEMPTS: usize = 32;
+
+/// Why a [`MapGenerator`] couldn't make a map.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GeneratorError {
+    /// The width or height isn't a positive, finite number.
+    InvalidSize,
+    /// The clearance isn't finite, or is narrower than the longer side of a
+    /// [`SPAWN_CAR_SIZE`] car.
+    InvalidClearance,
+    /// An obstacle side isn't a positive, finite number, or the smallest is
+    /// larger than the largest.
+    InvalidObstacleSize,
+    NoSpawns,
+    /// The generated map failed validation, like when the arena is too small for a
+    /// car to fit on the spawn points.
+    Map(MapError),
+}
+
+impl std::fmt::Display for GeneratorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GeneratorError::InvalidSize => write!(f, "The arena has an invalid size."),
+            GeneratorError::InvalidClearance => {
+                write!(f, "The clearance must be at least a car's length.")
+            }
+            GeneratorError::InvalidObstacleSize => {
+                write!(f, "The obstacle sizes must be positive, smallest first.")
+            }
+            GeneratorError::NoSpawns => write!(f, "There must be at least one spawn point."),
+            GeneratorError::Map(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for GeneratorError {}
+
+impl From<MapError> for GeneratorError {
+    fn from(error: MapError) -> Self {
+        GeneratorError::Map(error)
+    }
+}
+
+/// Builds random but fair arenas from a seed. The same settings and seed always
+/// give the same map.
+///
+/// Spawn points are spread evenly around the arena, all facing the centre, and
+/// obstacles are placed in pairs mirrored through the centre, so no spawn point
+/// has an easier arena than another. Every obstacle keeps at least `clearance` px
+/// from every other obstacle, the boundary and every spawn point, and is only
+/// kept if every spawn point can still drive to every other.
+#[derive(Debug, Clone)]
+pub struct MapGenerator {
+    pub width: f64,
+    pub height: f64,
+    /// How many obstacles to try to place. Rounded up to an even number.
+    pub obstacles: usize,
+    pub spawns: usize,
+    /// The narrowest gap, in px, left anywhere a car has to fit. At least the longer
+    /// side of a [`SPAWN_CAR_SIZE`] car.
+    pub clearance: f64,
+    /// The smallest and largest side, in px, of an obstacle.
+    pub obstacle_size: (f64, f64),
+}
+
+impl Default for MapGenerator {
+    fn default() -> Self {
+        MapGenerator {
+            width: DEFAULT_ARENA_WIDTH,
+            height: DEFAULT_ARENA_HEIGHT,
+            obstacles: 10,
+            spawns: 4,
+            clearance: 100.,
+            obstacle_size: (40., 200.),
+        }
+    }
+}
+
+impl MapGenerator {
+    pub fn with_size(self, width: f64, height: f64) -> Self {
+        MapGenerator {
+            width,
+            height,
+            ..self
+        }
+    }
+
+    pub fn with_obstacles(self, obstacles: usize) -> Self {
+        MapGenerator { obstacles, ..self }
+    }
+
+    pub fn with_spawns(self, spawns: usize) -> Self {
+        MapGenerator { spawns, ..self }
+    }
+
+    pub fn with_clearance(self, clearance: f64) -> Self {
+        MapGenerator { clearance, ..self }
+    }
+
+    /// Check the settings can make a map at all.
+    pub fn validate(&self) -> Result<(), GeneratorError> {
+        let positive = |value: f64| value.is_finite() && value > 0.;
+        if !positive(self.width) || !positive(self.height) {
+            return Err(GeneratorError::InvalidSize);
+        }
+        // Reachability is checked on a grid of `clearance`-sized cells, so a car
+        // has to fit in one whichever way it's facing.
+        let (car_width, car_height) = SPAWN_CAR_SIZE;
+        if !self.clearance.is_finite() || self.clearance < car_width.max(car_height) {
+            return Err(GeneratorError::InvalidClearance);
+        }
+        let (small, large) = self.obstacle_size;
+        if !positive(small) || !positive(large) || small > large {
+            return Err(GeneratorError::InvalidObstacleSize);
+        }
+        if self.spawns == 0 {
+            return Err(GeneratorError::NoSpawns);
+        }
+        Ok(())
+    }
+
+    /// A map from these settings and `seed`, validated like any other.
+    pub fn generate(&self, seed: u64) -> Result<Map, GeneratorError> {
+        self.validate()?;
+        let mut rng = SplitMix64::new(seed);
+        let center = Corner {
+            x: self.width / 2.,
+            y: self.height / 2.,
+        };
+
+        let spawns = self.spawns(&mut rng, &center);
+        // Nothing may be placed where a car starts out, nor right in front of it.
+        let keep_clear = spawns
+            .iter()
+            .map(|spawn| {
+                Rectangle::new(
+                    spawn.position.x,
+                    spawn.position.y,
+                    2. * self.clearance,
+                    2. * self.clearance,
+                    0.,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let mut obstacles: Vec<Rectangle> = Vec::new();
+        for _ in 0..self.obstacles.div_ceil(2) {
+            for _ in 0..ATTEMPTS {
+                let (small, large) = self.obstacle_size;
+                let obstacle = Rectangle::new(
+                    rng.range(0., self.width),
+                    rng.range(0., self.height),
+                    rng.range(small, large),
+                    rng.range(small, large),
+                    rng.range(0., std::f64::consts::PI),
+                );
+                let mirrored = Rectangle {
+                    x: 2. * center.x - obstacle.x,
+                    y: 2. * center.y - obstacle.y,
+                    ..obstacle
+                };
+                if self.fits(&obstacle, &mirrored, &obstacles, &keep_clear)
+                    && self.reachable(&spawns, &obstacles, &[obstacle, mirrored])
+                {
+                    obstacles.push(obstacle);
+                    obstacles.push(mirrored);
+                    break;
+                }
+            }
+        }
+
+        let map = Map {
+            name: format!("Generated #{}", seed),
+            width: self.width,
+            height: self.height,
+            walls: Vec::new(),
+            obstacles: obstacles.iter().map(Shape::collider).collect(),
+            spawns,
+            surfaces: Vec::new(),
+            objectives: Vec::new(),
+            restitution: None,
+        };
+        map.validate()?;
+        Ok(map)
+    }
+
+    /// Spawn points evenly spaced around an ellipse inset from the boundary,
+    /// starting from a random angle, each facing the centre.
+    fn spawns(&self, rng: &mut SplitMix64, center: &Corner) -> Vec<Spawn> {
+        let inset = self.clearance * 1.5;
+        let (radius_x, radius_y) = ((center.x - inset).max(0.), (center.y - inset).max(0.));
+        let phase = rng.range(0., std::f64::consts::TAU);
+        (0..self.spawns)
+            .map(|i| {
+                let around = phase + std::f64::consts::TAU * i as f64 / self.spawns as f64;
+                let position = Corner {
+                    x: center.x + radius_x * around.cos(),
+                    y: center.y + radius_y * around.sin(),
+                };
+                // A car facing `angle` heads along (-sin, -cos).
+                let towards = center.sub(&position);
+                let angle = (-towards.x)
+                    .atan2(-towards.y)
+                    .rem_euclid(std::f64::consts::TAU);
+                Spawn { position, angle }
+            })
+            .collect()
+    }
+
+    /// Whether the pair of obstacles keeps its clearances from the boundary, each
+    /// other, what's already placed and the spawn points.
+    fn fits(
+        &self,
+        obstacle: &Rectangle,
+        mirrored: &Rectangle,
+        placed: &[Rectangle],
+        keep_clear: &[Rectangle],
+    ) -> bool {
+        let inflated = |rectangle: &Rectangle| Rectangle {
+            width: rectangle.width + 2. * self.clearance,
+            height: rectangle.height + 2. * self.clearance,
+            ..*rectangle
+        };
+        let bounds = obstacle.aabb();
+        let inside = bounds.min.x >= self.clearance
+            && bounds.min.y >= self.clearance
+            && bounds.max.x <= self.width - self.clearance
+            && bounds.max.y <= self.height - self.clearance;
+
+        inside
+            && !inflated(obstacle).intersects(mirrored)
+            && [obstacle, mirrored].iter().all(|candidate| {
+                let grown = inflated(candidate);
+                placed.iter().all(|other| !grown.intersects(other))
+                    && keep_clear.iter().all(|spawn| !candidate.intersects(spawn))
+            })
+    }
+
+    /// Whether every spawn point can still reach every other with `extra` placed
+    /// on top of `placed`. The arena is cut into cells `clearance` px wide, and a
+    /// car can drive through any cell that no obstacle touches.
+    fn reachable(&self, spawns: &[Spawn], placed: &[Rectangle], extra: &[Rectangle]) -> bool {
+        let size = self.clearance;
+        let (columns, rows) = (
+            (self.width / size).ceil() as usize,
+            (self.height / size).ceil() as usize,
+        );
+        let cell_of = |point: &Corner| {
+            let column = ((point.x / size) as usize).min(columns - 1);
+            let row = ((point.y / size) as usize).min(rows - 1);
+            row * columns + column
+        };
+        let open = (0..columns * rows)
+            .map(|index| {
+                let cell = Rectangle::new(
+                    ((index % columns) as f64 + 0.5) * size,
+                    ((index / columns) as f64 + 0.5) * size,
+                    size,
+                    size,
+                    0.,
+                );
+                placed
+                    .iter()
+                    .chain(extra)
+                    .all(|obstacle| !cell.intersects(obstacle))
+            })
+            .collect::<Vec<_>>();
+
+        let start = match spawns.first() {
+            Some(spawn) => cell_of(&spawn.position),
+            None => return true,
+        };
+        let mut seen = vec![false; open.len()];
+        let mut frontier = vec![start];
+        seen[start] = true;
+        while let Some(index) = frontier.pop() {
+            let (column, row) = (index % columns, index / columns);
+            let neighbours = [
+                (column > 0).then(|| index - 1),
+                (column + 1 < columns).then(|| index + 1),
+                (row > 0).then(|| index - columns),
+                (row + 1 < rows).then(|| index + columns),
+            ];
+            for next in neighbours.into_iter().flatten() {
+                if open[next] && !seen[next] {
+                    seen[next] = true;
+                    frontier.push(next);
+                }
+            }
+        }
+        spawns.iter().all(|spawn| seen[cell_of(&spawn.position)])
+    }
+}
+
+impl Map {
+    /// A random but fair arena with the default [`MapGenerator`] settings.
+    pub fn generate(seed: u64) -> Result<Map, GeneratorError> {
+        MapGenerator::default().generate(seed)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Car, Collider};
+
+    #[test]
+    fn test_same_seed_same_map() {
+        let a = Map::generate(42).unwrap();
+        let b = Map::generate(42).unwrap();
+        let c = Map::generate(43).unwrap();
+        let outlines = |map: &Map| {
+            map.obstacles
+                .iter()
+                .flat_map(|obstacle| obstacle.outline(4))
+                .map(|corner| (corner.x, corner.y))
+                .collect::<Vec<_>>()
+        };
+        assert!(!a.obstacles.is_empty());
+        assert_eq!(outlines(&a), outlines(&b));
+        assert_ne!(outlines(&a), outlines(&c));
+    }
+
+    #[test]
+    fn test_generated_maps_are_fair_and_clear() {
+        let generator = MapGenerator::default().with_obstacles(16);
+        for seed in 0..20 {
+            let map = generator.generate(seed).unwrap();
+            assert_eq!(map.spawns.len(), generator.spawns);
+
+            let polygons = map
+                .obstacles
+                .iter()
+                .map(|obstacle| match obstacle {
+                    Collider::Polygon(polygon) => polygon.clone(),
+                    _ => unreachable!(),
+                })
+                .collect::<Vec<_>>();
+            for (i, a) in polygons.iter().enumerate() {
+                for b in &polygons[i + 1..] {
+                    assert!(!a.intersects(b));
+                }
+            }
+
+            // A car on every spawn point starts out clear of every obstacle, facing
+            // the centre.
+            let arena = map.arena();
+            for spawn in &map.spawns {
+                let car =
+                    Car::new(spawn.position.x, spawn.position.y, 60., 80.).with_angle(spawn.angle);
+                assert!(!arena.collides(&car));
+                let to_centre = Corner {
+                    x: map.width / 2.,
+                    y: map.height / 2.,
+                }
+                .sub(&spawn.position);
+                assert!(car.heading().dot(&to_centre.normalized()) > 0.999);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reject_settings_that_cannot_make_a_map() {
+        let generator = MapGenerator::default();
+        for clearance in [0., -10., 40., f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                generator
+                    .clone()
+                    .with_clearance(clearance)
+                    .generate(1)
+                    .unwrap_err(),
+                GeneratorError::InvalidClearance
+            );
+        }
+        assert_eq!(
+            generator.clone().with_spawns(0).generate(1).unwrap_err(),
+            GeneratorError::NoSpawns
+        );
+        assert_eq!(
+            generator
+                .clone()
+                .with_size(0., 500.)
+                .generate(1)
+                .unwrap_err(),
+            GeneratorError::InvalidSize
+        );
+        let backwards = MapGenerator {
+            obstacle_size: (200., 40.),
+            ..generator.clone()
+        };
+        assert_eq!(
+            backwards.generate(1).unwrap_err(),
+            GeneratorError::InvalidObstacleSize
+        );
+        // Too small for a car to fit anywhere.
+        assert_eq!(
+            generator.with_size(60., 60.).generate(1).unwrap_err(),
+            GeneratorError::Map(MapError::SpawnBlocked(0))
+        );
+    }
+
+    #[test]
+    fn test_gaps_narrower_than_a_car_are_closed() {
+        // A wall down the middle of the arena with a gap `gap` px wide halfway down.
+        let split = |gap: f64| {
+            let length = (1000. - gap) / 2.;
+            [
+                Rectangle::new(500., length / 2., 10., length, 0.),
+                Rectangle::new(500., 1000. - length / 2., 10., length, 0.),
+            ]
+        };
+        let spawns = [
+            Spawn {
+                position: Corner { x: 200., y: 500. },
+                angle: 0.,
+            },
+            Spawn {
+                position: Corner { x: 800., y: 500. },
+                angle: 0.,
+            },
+        ];
+        let generator = MapGenerator::default().with_size(1000., 1000.);
+        assert!(!generator.reachable(&spawns, &[], &split(50.)));
+        assert!(generator.reachable(&spawns, &[], &split(300.)));
+    }
+}
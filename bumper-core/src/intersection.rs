@@ -1,3 +1,5 @@
+use serde_derive::{Deserialize, Serialize};
+
 #[derive(Debug, Clone, Copy)]
 pub struct Rectangle {
     pub x: f64,
@@ -44,76 +46,491 @@ impl Rectangle {
     }
 
     pub fn edges(&self) -> Vec<Edge> {
-        let mut edges = Vec::new();
-        let vertices = self.vertices();
+        edges_of(&self.vertices()).collect()
+    }
 
-        for i in 0..vertices.len() {
-            let edge = Edge {
-                start: vertices[i],
-                end: vertices[(i + 1) % vertices.len()],
-            };
-            edges.push(edge);
+    pub fn polygon(&self) -> ConvexPolygon {
+        ConvexPolygon {
+            vertices: self.vertices(),
         }
-        edges
     }
 
     pub fn intersects(&self, rect: &Rectangle) -> bool {
-        let mut min_a: f64 = f64::INFINITY;
-        let mut max_a: f64 = f64::NEG_INFINITY;
-        let mut min_b: f64 = f64::INFINITY;
-        let mut max_b: f64 = f64::NEG_INFINITY;
-
-        for edge in self.edges() {
-            let normal = edge.normal();
-            for vertex in self.vertices() {
-                let projected = normal.x * vertex.x + normal.y * vertex.y;
-                min_a = min_a.min(projected);
-                max_a = max_a.max(projected);
-            }
-            for vertex in rect.vertices() {
-                let projected = normal.x * vertex.x + normal.y * vertex.y;
-                min_b = min_b.min(projected);
-                max_b = max_b.max(projected);
-            }
+        polygon_intersects(&self.vertices(), &rect.vertices())
+    }
+
+    /// How `self` and `rect` overlap, or `None` if they don't.
+    ///
+    /// The contact normal points from `self` towards `rect`, so translating `rect`
+    /// by [`Contact::translation`] (or `self` by its negation) separates them.
+    pub fn contact(&self, rect: &Rectangle) -> Option<Contact> {
+        polygon_contact(&self.vertices(), &rect.vertices())
+    }
+
+    /// Where `ray` first meets the rectangle's outline.
+    pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        nearest_hit(self.edges().iter().map(|edge| edge.raycast(ray)))
+    }
+}
+
+/// A convex polygon in world coordinates. [`Rectangle`] is the special case with
+/// four right-angled corners.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "Vec<Corner>", into = "Vec<Corner>")]
+pub struct ConvexPolygon {
+    vertices: Vec<Corner>,
+}
 
-            if max_a < min_b || max_b < min_a {
-                return false;
+/// Why a list of vertices doesn't make a [`ConvexPolygon`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonError {
+    TooFewVertices,
+    /// A coordinate is NaN or infinite.
+    NotFinite,
+    /// Two neighbouring vertices coincide, or three lie on a line.
+    Degenerate,
+    /// The outline turns both ways, or winds round more than once.
+    NotConvex,
+}
+
+impl std::fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PolygonError::TooFewVertices => write!(f, "A polygon needs at least 3 vertices."),
+            PolygonError::NotFinite => write!(f, "Polygon vertices must be finite numbers."),
+            PolygonError::Degenerate => {
+                write!(f, "Polygon vertices must not repeat or lie on a line.")
             }
+            PolygonError::NotConvex => write!(f, "The polygon is not convex."),
         }
-        for edge in rect.edges() {
-            let normal = edge.normal();
-            for vertex in self.vertices() {
-                let projected = normal.x * vertex.x + normal.y * vertex.y;
-                min_a = min_a.min(projected);
-                max_a = max_a.max(projected);
+    }
+}
+
+impl std::error::Error for PolygonError {}
+
+impl TryFrom<Vec<Corner>> for ConvexPolygon {
+    type Error = PolygonError;
+
+    fn try_from(vertices: Vec<Corner>) -> Result<Self, Self::Error> {
+        let polygon = ConvexPolygon::new(vertices);
+        polygon.validate()?;
+        Ok(polygon)
+    }
+}
+
+impl From<ConvexPolygon> for Vec<Corner> {
+    fn from(polygon: ConvexPolygon) -> Self {
+        polygon.vertices
+    }
+}
+
+impl From<&Rectangle> for ConvexPolygon {
+    fn from(rect: &Rectangle) -> Self {
+        rect.polygon()
+    }
+}
+
+impl ConvexPolygon {
+    /// The vertices must describe a convex polygon, in either winding order. Use
+    /// `ConvexPolygon::try_from` to have them checked.
+    pub fn new(vertices: Vec<Corner>) -> Self {
+        ConvexPolygon {
+            vertices: wound(vertices),
+        }
+    }
+
+    pub fn vertices(&self) -> &[Corner] {
+        &self.vertices
+    }
+
+    pub fn edges(&self) -> Vec<Edge> {
+        edges_of(&self.vertices).collect()
+    }
+
+    pub fn centroid(&self) -> Corner {
+        centroid(&self.vertices)
+    }
+
+    /// Whether the vertices make a proper convex polygon: see [`ConvexPolygon::validate`].
+    pub fn is_convex(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Check there are at least three finite vertices, every corner turns the same
+    /// way by a non-zero angle and the outline goes round exactly once.
+    pub fn validate(&self) -> Result<(), PolygonError> {
+        let n = self.vertices.len();
+        if n < 3 {
+            return Err(PolygonError::TooFewVertices);
+        }
+        if !self
+            .vertices
+            .iter()
+            .all(|vertex| vertex.x.is_finite() && vertex.y.is_finite())
+        {
+            return Err(PolygonError::NotFinite);
+        }
+        let mut turned = 0.;
+        for i in 0..n {
+            let (a, b, c) = (
+                self.vertices[i],
+                self.vertices[(i + 1) % n],
+                self.vertices[(i + 2) % n],
+            );
+            let (incoming, outgoing) = (b.sub(&a), c.sub(&b));
+            let cross = incoming.cross(&outgoing);
+            // Relative to the edge lengths, so the test doesn't depend on scale.
+            if cross.abs() <= 1e-9 * incoming.length() * outgoing.length() {
+                return Err(PolygonError::Degenerate);
             }
-            for vertex in rect.vertices() {
-                let projected = normal.x * vertex.x + normal.y * vertex.y;
-                min_b = min_b.min(projected);
-                max_b = max_b.max(projected);
+            // `wound` orders the vertices so that a convex outline turns negatively.
+            if cross > 0. {
+                return Err(PolygonError::NotConvex);
             }
+            turned += cross.atan2(incoming.dot(&outgoing));
+        }
+        // A star turns the same way at every corner but goes round twice or more.
+        if turned < -std::f64::consts::TAU - 1e-6 {
+            return Err(PolygonError::NotConvex);
+        }
+        Ok(())
+    }
 
-            if max_a < min_b || max_b < min_a {
-                return false;
-            }
+    /// Whether `point` lies inside the polygon or on its boundary.
+    pub fn contains(&self, point: &Corner) -> bool {
+        edges_of(&self.vertices).all(|edge| point.sub(&edge.start).dot(&edge.normal()) <= 0.)
+    }
+
+    pub fn intersects(&self, polygon: &ConvexPolygon) -> bool {
+        polygon_intersects(&self.vertices, &polygon.vertices)
+    }
+
+    /// How `self` and `polygon` overlap, with the normal pointing towards `polygon`.
+    pub fn contact(&self, polygon: &ConvexPolygon) -> Option<Contact> {
+        polygon_contact(&self.vertices, &polygon.vertices)
+    }
+
+    /// Where `ray` first meets the polygon's outline.
+    pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        nearest_hit(edges_of(&self.vertices).map(|edge| edge.raycast(ray)))
+    }
+}
+
+/// Contact data for a pair of overlapping shapes.
+#[derive(Debug, Clone)]
+pub struct Contact {
+    /// Unit separating axis, pointing from the first shape towards the second.
+    pub normal: Corner,
+    /// How far the shapes overlap along `normal`.
+    pub depth: f64,
+    /// The one or two points where the shapes touch.
+    pub points: Vec<Corner>,
+}
+
+impl Contact {
+    /// The minimum translation vector: the smallest move of the second shape that
+    /// separates it from the first.
+    pub fn translation(&self) -> Corner {
+        self.normal.scale(self.depth)
+    }
+
+    /// The average of the contact points.
+    pub fn point(&self) -> Corner {
+        centroid(&self.points)
+    }
+
+    /// The same contact seen from the second shape.
+    pub fn flipped(self) -> Contact {
+        Contact {
+            normal: self.normal.scale(-1.),
+            ..self
         }
-        true
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+fn centroid(vertices: &[Corner]) -> Corner {
+    let sum = vertices
+        .iter()
+        .fold(Corner { x: 0., y: 0. }, |sum, vertex| sum.add(vertex));
+    sum.scale(1. / vertices.len().max(1) as f64)
+}
+
+/// `vertices` of a convex polygon, reordered if need be so that [`Edge::normal`]
+/// points out of the polygon along every edge.
+pub(crate) fn wound(mut vertices: Vec<Corner>) -> Vec<Corner> {
+    let signed_area = edges_of(&vertices)
+        .map(|edge| edge.start.cross(&edge.end))
+        .sum::<f64>();
+    if signed_area > 0. {
+        vertices.reverse();
+    }
+    vertices
+}
+
+pub(crate) fn edges_of(vertices: &[Corner]) -> impl Iterator<Item = Edge> + '_ {
+    (0..vertices.len()).map(move |i| Edge {
+        start: vertices[i],
+        end: vertices[(i + 1) % vertices.len()],
+    })
+}
+
+/// How far the extent `b` must move along an axis to clear the extent `a`, signed
+/// by the shorter direction, or `None` if they don't overlap.
+pub(crate) fn axis_penetration(
+    (min_a, max_a): (f64, f64),
+    (min_b, max_b): (f64, f64),
+) -> Option<f64> {
+    if max_a < min_b || max_b < min_a {
+        return None;
+    }
+    let (forwards, backwards) = (max_a - min_b, max_b - min_a);
+    Some(if forwards <= backwards {
+        forwards
+    } else {
+        -backwards
+    })
+}
+
+pub(crate) fn polygon_intersects(a: &[Corner], b: &[Corner]) -> bool {
+    edges_of(a).chain(edges_of(b)).all(|edge| {
+        let normal = edge.normal();
+        let (min_a, max_a) = normal.project(a);
+        let (min_b, max_b) = normal.project(b);
+        !(max_a < min_b || max_b < min_a)
+    })
+}
+
+/// Separating axis test between two convex polygons, whose vertices are wound so
+/// that [`Edge::normal`] points outwards, followed by clipping the incident edge
+/// against the reference edge to find the contact points.
+pub(crate) fn polygon_contact(a: &[Corner], b: &[Corner]) -> Option<Contact> {
+    let mut best: Option<(Corner, f64)> = None;
+
+    for edge in edges_of(a).chain(edges_of(b)) {
+        let axis = edge.normal().normalized();
+        let push = axis_penetration(axis.project(a), axis.project(b))?;
+        if best.is_none_or(|(_, depth)| push.abs() < depth) {
+            best = Some((axis.scale(push.signum()), push.abs()));
+        }
+    }
+
+    let (normal, depth) = best?;
+
+    // The reference edge is whichever face lines up best with the normal; the
+    // incident edge is the other shape's face that opposes it the most.
+    let best_face = |vertices: &[Corner], direction: &Corner| {
+        edges_of(vertices)
+            .max_by(|e1, e2| {
+                let d1 = e1.normal().normalized().dot(direction);
+                let d2 = e2.normal().normalized().dot(direction);
+                d1.total_cmp(&d2)
+            })
+            .expect("Polygon has no edges.")
+    };
+    let reversed = normal.scale(-1.);
+    let face_a = best_face(a, &normal);
+    let face_b = best_face(b, &reversed);
+
+    let (reference, incident, reference_normal, incident_vertices) =
+        if face_a.normal().normalized().dot(&normal)
+            >= face_b.normal().normalized().dot(&reversed) - 1e-9
+        {
+            (face_a, face_b, normal, b)
+        } else {
+            (face_b, face_a, reversed, a)
+        };
+
+    let tangent = reference.end.sub(&reference.start).normalized();
+    let clipped = clip(
+        &[incident.start, incident.end],
+        &tangent.scale(-1.),
+        -tangent.dot(&reference.start),
+    );
+    let clipped = clip(&clipped, &tangent, tangent.dot(&reference.end));
+
+    let face_offset = reference_normal.dot(&reference.start);
+    let mut points = clipped
+        .into_iter()
+        .filter(|point| reference_normal.dot(point) - face_offset <= 1e-9)
+        .collect::<Vec<_>>();
+
+    if points.is_empty() {
+        // Numerically degenerate; fall back to the deepest incident vertex.
+        let deepest = incident_vertices
+            .iter()
+            .min_by(|v1, v2| {
+                reference_normal
+                    .dot(v1)
+                    .total_cmp(&reference_normal.dot(v2))
+            })
+            .expect("Polygon has no vertices.");
+        points.push(*deepest);
+    }
+
+    Some(Contact {
+        normal,
+        depth,
+        points,
+    })
+}
+
+/// Keep the part of the segment `points` where `normal · p <= offset`.
+fn clip(points: &[Corner], normal: &Corner, offset: f64) -> Vec<Corner> {
+    let mut clipped = Vec::with_capacity(2);
+    if points.len() < 2 {
+        return points
+            .iter()
+            .filter(|point| normal.dot(point) <= offset)
+            .copied()
+            .collect();
+    }
+    let (v0, v1) = (points[0], points[1]);
+    let (d0, d1) = (normal.dot(&v0) - offset, normal.dot(&v1) - offset);
+    if d0 <= 0. {
+        clipped.push(v0);
+    }
+    if d1 <= 0. {
+        clipped.push(v1);
+    }
+    if d0 * d1 < 0. {
+        clipped.push(v0.add(&v1.sub(&v0).scale(d0 / (d0 - d1))));
+    }
+    clipped
+}
+
+/// A half-line from `origin` along the unit vector `direction`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Ray {
+    pub origin: Corner,
+    pub direction: Corner,
+}
+
+impl Ray {
+    /// `direction` doesn't have to be normalized.
+    pub fn new(origin: Corner, direction: Corner) -> Self {
+        Ray {
+            origin,
+            direction: direction.normalized(),
+        }
+    }
+
+    /// A ray from `origin` heading along `angle`, in the same sense as a car's
+    /// [`heading`](crate::Car::heading).
+    pub fn from_angle(origin: Corner, angle: f64) -> Self {
+        Ray::new(
+            origin,
+            Corner {
+                x: -angle.sin(),
+                y: -angle.cos(),
+            },
+        )
+    }
+
+    /// The point `distance` along the ray.
+    pub fn at(&self, distance: f64) -> Corner {
+        self.origin.add(&self.direction.scale(distance))
+    }
+}
+
+/// Where a [`Ray`] met something.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct RayHit {
+    /// How far along the ray the hit is.
+    pub distance: f64,
+    pub point: Corner,
+    /// Unit normal of the surface that was hit, facing back along the ray.
+    pub normal: Corner,
+}
+
+impl RayHit {
+    pub(crate) fn new(ray: &Ray, distance: f64, normal: Corner) -> Self {
+        let normal = normal.normalized();
+        RayHit {
+            distance,
+            point: ray.at(distance),
+            normal: if normal.dot(&ray.direction) > 0. {
+                normal.scale(-1.)
+            } else {
+                normal
+            },
+        }
+    }
+}
+
+pub(crate) fn nearest_hit(hits: impl Iterator<Item = Option<RayHit>>) -> Option<RayHit> {
+    hits.flatten()
+        .min_by(|a, b| a.distance.total_cmp(&b.distance))
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Corner {
     pub x: f64,
     pub y: f64,
 }
 
+impl Corner {
+    pub fn add(&self, other: &Corner) -> Corner {
+        Corner {
+            x: self.x + other.x,
+            y: self.y + other.y,
+        }
+    }
+
+    pub fn sub(&self, other: &Corner) -> Corner {
+        Corner {
+            x: self.x - other.x,
+            y: self.y - other.y,
+        }
+    }
+
+    pub fn dot(&self, other: &Corner) -> f64 {
+        self.x * other.x + self.y * other.y
+    }
+
+    /// The z component of the 3D cross product of the two vectors.
+    pub fn cross(&self, other: &Corner) -> f64 {
+        self.x * other.y - self.y * other.x
+    }
+
+    pub fn length(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn scale(&self, factor: f64) -> Corner {
+        Corner {
+            x: self.x * factor,
+            y: self.y * factor,
+        }
+    }
+
+    /// This vector scaled to unit length, or unchanged if it has no length.
+    pub fn normalized(&self) -> Corner {
+        let length = self.length();
+        if length == 0. {
+            *self
+        } else {
+            self.scale(1. / length)
+        }
+    }
+
+    /// The `(min, max)` extent of `vertices` projected onto this axis.
+    pub fn project(&self, vertices: &[Corner]) -> (f64, f64) {
+        vertices
+            .iter()
+            .map(|vertex| self.dot(vertex))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), p| {
+                (min.min(p), max.max(p))
+            })
+    }
+}
+
 impl std::fmt::Display for Corner {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         write!(f, "Corner({:.4}, {:.4})", self.x, self.y)
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct Edge {
     pub start: Corner,
     pub end: Corner,
@@ -127,13 +544,30 @@ impl Edge {
             (self.end.y - self.start.y) / (self.end.x - self.start.x)
         }
     }
+    /// Perpendicular to the edge; points outward for the winding used by [`Rectangle::vertices`].
     pub fn normal(&self) -> Corner {
         Corner {
-            y: self.end.y - self.start.y,
-            x: self.start.x - self.end.x,
+            x: self.start.y - self.end.y,
+            y: self.end.x - self.start.x,
         }
     }
 
+    /// Where `ray` crosses the edge, or `None` if it misses or runs parallel to it.
+    pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        let along = self.end.sub(&self.start);
+        let denominator = ray.direction.cross(&along);
+        if denominator.abs() < 1e-12 {
+            return None;
+        }
+        let to_start = self.start.sub(&ray.origin);
+        let distance = to_start.cross(&along) / denominator;
+        let across = to_start.cross(&ray.direction) / denominator;
+        if distance < 0. || !(0. ..=1.).contains(&across) {
+            return None;
+        }
+        Some(RayHit::new(ray, distance, self.normal()))
+    }
+
     pub fn y_intercept(&self) -> f64 {
         let slope = self.slope();
         if slope == f64::INFINITY {
@@ -175,4 +609,152 @@ pub mod tests {
         let rect2 = Rectangle::new(0., 0., 6., 10., std::f64::consts::TAU);
         assert!(rect1.intersects(&rect2));
     }
+
+    #[test]
+    fn test_triangle_against_rectangle() {
+        let square = Rectangle::new(0., 0., 10., 10., 0.).polygon();
+        let triangle = ConvexPolygon::new(vec![
+            Corner { x: 4., y: 0. },
+            Corner { x: 12., y: -4. },
+            Corner { x: 12., y: 4. },
+        ]);
+        assert!(triangle.is_convex());
+        let contact = square.contact(&triangle).unwrap();
+        assert!((contact.depth - 1.).abs() < 1e-9);
+        assert!((contact.normal.x - 1.).abs() < 1e-9);
+
+        let far = ConvexPolygon::new(vec![
+            Corner { x: 6., y: 0. },
+            Corner { x: 12., y: -4. },
+            Corner { x: 12., y: 4. },
+        ]);
+        assert!(!square.intersects(&far));
+    }
+
+    #[test]
+    fn test_degenerate_and_concave_polygons_are_rejected() {
+        let corners = |points: &[(f64, f64)]| {
+            points
+                .iter()
+                .map(|&(x, y)| Corner { x, y })
+                .collect::<Vec<_>>()
+        };
+        let square = corners(&[(0., 0.), (10., 0.), (10., 10.), (0., 10.)]);
+        assert!(ConvexPolygon::try_from(square).is_ok());
+
+        let cases = [
+            (
+                corners(&[(0., 0.), (10., 0.)]),
+                PolygonError::TooFewVertices,
+            ),
+            (
+                corners(&[(0., 0.), (f64::NAN, 0.), (0., 10.)]),
+                PolygonError::NotFinite,
+            ),
+            (
+                corners(&[(0., 0.), (5., 0.), (10., 0.), (10., 10.)]),
+                PolygonError::Degenerate,
+            ),
+            (
+                corners(&[(0., 0.), (0., 0.), (10., 0.), (0., 10.)]),
+                PolygonError::Degenerate,
+            ),
+            (
+                corners(&[(0., 0.), (10., 0.), (5., 2.), (10., 10.), (0., 10.)]),
+                PolygonError::NotConvex,
+            ),
+        ];
+        for (vertices, error) in cases {
+            assert_eq!(ConvexPolygon::try_from(vertices).unwrap_err(), error);
+        }
+
+        // A pentagram turns the same way at every point, but winds round twice.
+        let star = (0..5)
+            .map(|i| {
+                let angle = std::f64::consts::TAU * (2 * i) as f64 / 5.;
+                Corner {
+                    x: angle.cos(),
+                    y: angle.sin(),
+                }
+            })
+            .collect::<Vec<_>>();
+        assert!(!ConvexPolygon::new(star.clone()).is_convex());
+        let json = serde_json::to_string(&star).unwrap();
+        assert!(serde_json::from_str::<ConvexPolygon>(&json).is_err());
+    }
+
+    #[test]
+    fn test_rotated_rectangles_do_not_intersect() {
+        // Their bounding boxes overlap, but the diamond's edge clears the corner.
+        let rect1 = Rectangle::new(0., 0., 10., 10., 0.);
+        let rect2 = Rectangle::new(11., 11., 10., 10., std::f64::consts::FRAC_PI_4);
+        assert!(!rect1.intersects(&rect2));
+        assert!(rect1.contact(&rect2).is_none());
+    }
+
+    #[test]
+    fn test_contact_is_shallowest_axis() {
+        let rect1 = Rectangle::new(0., 0., 10., 10., 0.);
+        let rect2 = Rectangle::new(8., 1., 10., 10., 0.);
+        let contact = rect1.contact(&rect2).unwrap();
+        assert!((contact.normal.x - 1.).abs() < 1e-9 && contact.normal.y.abs() < 1e-9);
+        assert!((contact.depth - 2.).abs() < 1e-9);
+        assert_eq!(contact.points.len(), 2);
+        for point in &contact.points {
+            assert!((3. - 1e-9..=5. + 1e-9).contains(&point.x));
+            assert!((-4. - 1e-9..=5. + 1e-9).contains(&point.y));
+        }
+        assert!(rect1
+            .contact(&Rectangle::new(20., 0., 10., 10., 0.))
+            .is_none());
+    }
+
+    #[test]
+    fn test_contact_point_at_poking_corner() {
+        // A diamond whose left corner pokes 1 unit into the square's right face.
+        let half_diagonal = 5. * std::f64::consts::SQRT_2;
+        let rect1 = Rectangle::new(0., 0., 10., 10., 0.);
+        let rect2 = Rectangle::new(
+            4. + half_diagonal,
+            0.,
+            10.,
+            10.,
+            std::f64::consts::FRAC_PI_4,
+        );
+        let contact = rect1.contact(&rect2).unwrap();
+        assert!((contact.depth - 1.).abs() < 1e-9);
+        assert_eq!(contact.points.len(), 1);
+        assert!((contact.points[0].x - 4.).abs() < 1e-9);
+        assert!(contact.points[0].y.abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_raycast_rectangle() {
+        let rect = Rectangle::new(0., 0., 10., 10., 0.);
+        let ray = Ray::new(Corner { x: -20., y: 1. }, Corner { x: 3., y: 0. });
+        let hit = rect.raycast(&ray).unwrap();
+        assert!((hit.distance - 15.).abs() < 1e-9);
+        assert!((hit.point.x + 5.).abs() < 1e-9);
+        assert!((hit.normal.x + 1.).abs() < 1e-9);
+
+        // Starting inside, the ray hits the far side on its way out.
+        let hit = rect
+            .raycast(&Ray::new(Corner { x: 0., y: 0. }, Corner { x: 0., y: 1. }))
+            .unwrap();
+        assert!((hit.distance - 5.).abs() < 1e-9);
+        assert!((hit.normal.y + 1.).abs() < 1e-9);
+
+        assert!(rect
+            .raycast(&Ray::new(
+                Corner { x: -20., y: 1. },
+                Corner { x: -1., y: 0. }
+            ))
+            .is_none());
+        assert!(rect
+            .raycast(&Ray::new(
+                Corner { x: -20., y: 6. },
+                Corner { x: 1., y: 0. }
+            ))
+            .is_none());
+    }
 }
@@ -1,8 +1,39 @@
+mod arena;
+mod bot;
+mod broad_phase;
 mod car;
+mod ccd;
+mod driver;
+mod generate;
 mod intersection;
+mod map;
+mod rng;
+mod sensor;
+mod shape;
+mod surface;
+mod tiled;
+mod timestep;
+mod vehicle;
+mod world;
+mod xml;
 
 #[cfg(test)]
 pub mod tests;
 
+pub use arena::*;
+pub use bot::*;
+pub use broad_phase::*;
 pub use car::*;
+pub use ccd::*;
+pub use driver::*;
+pub use generate::*;
 pub use intersection::*;
+pub use map::*;
+pub use rng::*;
+pub use sensor::*;
+pub use shape::*;
+pub use surface::*;
+pub use tiled::*;
+pub use timestep::*;
+pub use vehicle::*;
+pub use world::*;
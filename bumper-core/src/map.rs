@@ -0,0 +1,334 @@
+use crate::{Arena, Car, Collider, Corner, Surface, SurfaceZone, Wall};
+use serde_derive::{Deserialize, Serialize};
+
+/// The map that ships with the game.
+const DEFAULT_MAP: &str = include_str!("../maps/default.json");
+
+/// The width and height, in px, of the car [`Map::validate`] makes sure fits on
+/// every spawn point: the standard vehicle class.
+pub const SPAWN_CAR_SIZE: (f64, f64) = (60., 80.);
+
+/// Where a car can be placed when it joins.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Spawn {
+    pub position: Corner,
+    /// Which way the car faces, in the same sense as [`CarConfig::angle`](crate::CarConfig::angle).
+    #[serde(default)]
+    pub angle: f64,
+}
+
+/// A named region game modes can score against, like a hill to hold or a goal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Objective {
+    /// Unique within a [`Map`].
+    pub name: String,
+    pub area: Collider,
+}
+
+/// A level: everything needed to build an [`Arena`] plus where cars start and
+/// what they play for. The boundary walls are implied by the size; `walls` only
+/// lists the ones inside it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Map {
+    #[serde(default)]
+    pub name: String,
+    pub width: f64,
+    pub height: f64,
+    #[serde(default)]
+    pub walls: Vec<Wall>,
+    #[serde(default)]
+    pub obstacles: Vec<Collider>,
+    pub spawns: Vec<Spawn>,
+    #[serde(default)]
+    pub surfaces: Vec<SurfaceZone>,
+    #[serde(default)]
+    pub objectives: Vec<Objective>,
+    /// How bouncy the walls are. Uses the arena's default when left out.
+    #[serde(default)]
+    pub restitution: Option<f64>,
+}
+
+/// Why a map was rejected. Indices are into the matching list of the [`Map`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapError {
+    /// The file isn't valid JSON, or doesn't have the expected shape.
+    Parse(String),
+    /// The width or height isn't a positive, finite number.
+    InvalidSize,
+    InvalidRestitution,
+    /// A wall has no length, a non-positive thickness or a non-finite coordinate.
+    InvalidWall(usize),
+    /// An obstacle isn't convex, or has a non-positive radius or a non-finite coordinate.
+    InvalidObstacle(usize),
+    /// A surface zone isn't a valid area, or its custom handling has a negative or
+    /// non-finite multiplier.
+    InvalidSurface(usize),
+    InvalidObjective(usize),
+    DuplicateObjective(String),
+    NoSpawns,
+    /// A spawn point is outside the playfield.
+    SpawnOutside(usize),
+    /// A car on a spawn point would overlap a wall or obstacle.
+    SpawnBlocked(usize),
+}
+
+impl std::fmt::Display for MapError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapError::Parse(message) => write!(f, "Couldn't parse map: {}", message),
+            MapError::InvalidSize => write!(f, "The map has an invalid size."),
+            MapError::InvalidRestitution => {
+                write!(f, "The map's restitution must be within 0 and 1.")
+            }
+            MapError::InvalidWall(index) => write!(f, "Wall {} is invalid.", index),
+            MapError::InvalidObstacle(index) => write!(f, "Obstacle {} is invalid.", index),
+            MapError::InvalidSurface(index) => write!(f, "Surface zone {} is invalid.", index),
+            MapError::InvalidObjective(index) => write!(f, "Objective {} is invalid.", index),
+            MapError::DuplicateObjective(name) => {
+                write!(f, "Objective {:?} is defined more than once.", name)
+            }
+            MapError::NoSpawns => write!(f, "The map has no spawn points."),
+            MapError::SpawnOutside(index) => {
+                write!(f, "Spawn point {} is outside the playfield.", index)
+            }
+            MapError::SpawnBlocked(index) => {
+                write!(
+                    f,
+                    "A car on spawn point {} would hit a wall or obstacle.",
+                    index
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl Default for Map {
+    fn default() -> Self {
+        Map::from_json(DEFAULT_MAP).expect("The built-in map is invalid.")
+    }
+}
+
+impl Map {
+    /// Load and validate a map.
+    pub fn from_json(json: &str) -> Result<Self, MapError> {
+        let map: Map = serde_json::from_str(json).map_err(|e| MapError::Parse(e.to_string()))?;
+        map.validate()?;
+        Ok(map)
+    }
+
+    pub fn validate(&self) -> Result<(), MapError> {
+        let positive = |value: f64| value > 0. && value.is_finite();
+        if !positive(self.width) || !positive(self.height) {
+            return Err(MapError::InvalidSize);
+        }
+        if let Some(restitution) = self.restitution {
+            if !(0. ..=1.).contains(&restitution) {
+                return Err(MapError::InvalidRestitution);
+            }
+        }
+        if let Some(index) = self.walls.iter().position(|wall| !valid_wall(wall)) {
+            return Err(MapError::InvalidWall(index));
+        }
+        if let Some(index) = self
+            .obstacles
+            .iter()
+            .position(|obstacle| !valid_area(obstacle))
+        {
+            return Err(MapError::InvalidObstacle(index));
+        }
+        if let Some(index) = self.surfaces.iter().position(|zone| {
+            !valid_area(&zone.area) || zone.surface.is_some_and(|surface| !valid_surface(surface))
+        }) {
+            return Err(MapError::InvalidSurface(index));
+        }
+        for (index, objective) in self.objectives.iter().enumerate() {
+            if objective.name.trim().is_empty() || !valid_area(&objective.area) {
+                return Err(MapError::InvalidObjective(index));
+            }
+            if self.objectives[..index]
+                .iter()
+                .any(|other| other.name == objective.name)
+            {
+                return Err(MapError::DuplicateObjective(objective.name.clone()));
+            }
+        }
+
+        let (width, height) = SPAWN_CAR_SIZE;
+        self.validate_spawns(width, height)
+    }
+
+    /// Check there are spawn points, and that a `width` by `height` car placed on
+    /// each, facing its way, is inside the playfield and clear of every wall and
+    /// obstacle.
+    pub fn validate_spawns(&self, width: f64, height: f64) -> Result<(), MapError> {
+        if self.spawns.is_empty() {
+            return Err(MapError::NoSpawns);
+        }
+        let arena = self.arena();
+        for (index, spawn) in self.spawns.iter().enumerate() {
+            if !arena.contains(&spawn.position) || !spawn.angle.is_finite() {
+                return Err(MapError::SpawnOutside(index));
+            }
+            let car =
+                Car::new(spawn.position.x, spawn.position.y, width, height).with_angle(spawn.angle);
+            if arena.collides(&car) {
+                return Err(MapError::SpawnBlocked(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// The arena the map describes, boundary walls included.
+    pub fn arena(&self) -> Arena {
+        let mut arena = Arena::new(self.width, self.height);
+        arena.walls.extend(self.walls.iter().copied());
+        arena.obstacles = self.obstacles.clone();
+        arena.surfaces = self.surfaces.clone();
+        if let Some(restitution) = self.restitution {
+            arena.restitution = restitution;
+        }
+        arena
+    }
+
+    /// The spawn point for the `n`th car to join, going round the spawn points in turn.
+    pub fn spawn(&self, n: usize) -> Spawn {
+        self.spawns[n % self.spawns.len()]
+    }
+
+    pub fn objective(&self, name: &str) -> Option<&Objective> {
+        self.objectives
+            .iter()
+            .find(|objective| objective.name == name)
+    }
+}
+
+fn finite(point: &Corner) -> bool {
+    point.x.is_finite() && point.y.is_finite()
+}
+
+fn valid_wall(wall: &Wall) -> bool {
+    finite(&wall.start)
+        && finite(&wall.end)
+        && wall.end.sub(&wall.start).length() > 0.
+        && wall.thickness > 0.
+        && wall.thickness.is_finite()
+}
+
+fn valid_area(area: &Collider) -> bool {
+    let radius = |radius: f64| radius > 0. && radius.is_finite();
+    match area {
+        Collider::Circle(circle) => finite(&circle.center) && radius(circle.radius),
+        Collider::Capsule(capsule) => {
+            finite(&capsule.start) && finite(&capsule.end) && radius(capsule.radius)
+        }
+        Collider::Polygon(polygon) => polygon.vertices().iter().all(finite) && polygon.is_convex(),
+    }
+}
+
+fn valid_surface(surface: Surface) -> bool {
+    [
+        surface.friction,
+        surface.grip,
+        surface.acceleration,
+        surface.max_speed,
+    ]
+    .iter()
+    .all(|multiplier| multiplier.is_finite() && *multiplier >= 0.)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Circle, SurfaceKind};
+
+    #[test]
+    fn test_built_in_map_is_valid() {
+        let map = Map::default();
+        let arena = map.arena();
+        assert_eq!(arena.walls.len(), 4);
+        assert_eq!(arena.obstacles.len(), map.obstacles.len());
+        assert_eq!(arena.surfaces.len(), map.surfaces.len());
+        assert!(map.objective("centre").is_some());
+        assert_eq!(
+            map.spawn(map.spawns.len()).position.x,
+            map.spawn(0).position.x
+        );
+    }
+
+    #[test]
+    fn test_invalid_maps_are_rejected() {
+        let load = |extra: &str| {
+            Map::from_json(&format!(
+                r#"{{"width": 1000, "height": 1000, "spawns": [{{"position": {{"x": 100, "y": 100}}}}]{}}}"#,
+                extra
+            ))
+        };
+        assert!(load("").is_ok());
+        assert!(matches!(load(", \"walls\": 3"), Err(MapError::Parse(_))));
+        assert_eq!(
+            load(r#", "obstacles": [{"circle": {"center": {"x": 500, "y": 500}, "radius": -1}}]"#)
+                .unwrap_err(),
+            MapError::InvalidObstacle(0)
+        );
+        let zone = r#"{"area": {"circle": {"center": {"x": 500, "y": 500}, "radius": 50}},
+                        "kind": "mud", "surface": {"grip": -2}}"#;
+        assert_eq!(
+            load(&format!(r#", "surfaces": [{}]"#, zone)).unwrap_err(),
+            MapError::InvalidSurface(0)
+        );
+        let mut map = load("").unwrap();
+        map.surfaces.push(
+            SurfaceZone::new(
+                Circle::new(Corner { x: 500., y: 500. }, 50.),
+                SurfaceKind::Ice,
+            )
+            .with_surface(Surface {
+                max_speed: f64::NAN,
+                ..Default::default()
+            }),
+        );
+        assert_eq!(map.validate().unwrap_err(), MapError::InvalidSurface(0));
+        assert_eq!(
+            load(r#", "obstacles": [{"circle": {"center": {"x": 100, "y": 100}, "radius": 20}}]"#)
+                .unwrap_err(),
+            MapError::SpawnBlocked(0)
+        );
+        assert_eq!(
+            Map::from_json(
+                r#"{"width": 1000, "height": 1000, "spawns": [{"position": {"x": -5, "y": 100}}]}"#
+            )
+            .unwrap_err(),
+            MapError::SpawnOutside(0)
+        );
+        assert_eq!(
+            Map::from_json(r#"{"width": 1000, "height": 1000, "spawns": []}"#).unwrap_err(),
+            MapError::NoSpawns
+        );
+    }
+
+    #[test]
+    fn test_spawns_need_room_for_a_whole_car() {
+        // Neither obstacle covers the spawn point itself, only where the car would be.
+        let beside = Map::from_json(
+            r#"{"width": 1000, "height": 1000, "spawns": [{"position": {"x": 100, "y": 100}}],
+                "obstacles": [{"circle": {"center": {"x": 140, "y": 100}, "radius": 20}}]}"#,
+        );
+        assert_eq!(beside.unwrap_err(), MapError::SpawnBlocked(0));
+        let against_wall = Map::from_json(
+            r#"{"width": 1000, "height": 1000, "spawns": [{"position": {"x": 20, "y": 100}}]}"#,
+        );
+        assert_eq!(against_wall.unwrap_err(), MapError::SpawnBlocked(0));
+
+        // A car turned sideways is wider than it is long.
+        let map = Map::from_json(
+            r#"{"width": 1000, "height": 1000, "spawns": [{"position": {"x": 35, "y": 100}}]}"#,
+        )
+        .unwrap();
+        let mut sideways = map.clone();
+        sideways.spawns[0].angle = std::f64::consts::FRAC_PI_2;
+        assert_eq!(sideways.validate().unwrap_err(), MapError::SpawnBlocked(0));
+        assert!(map.validate_spawns(80., 80.).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+use crate::{Arena, Car, Ray};
+use serde_derive::{Deserialize, Serialize};
+
+/// A fan of distance sensors mounted at a car's centre, spread evenly over a
+/// field of view around its heading. A [`World`](crate::World) refreshes the
+/// readings of every car that has one on each step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedLidar")]
+pub struct Lidar {
+    /// How many rays there are. At least one.
+    pub rays: usize,
+    /// Angle, in radians, between the outermost rays. Within `[0, 2π]`.
+    pub field_of_view: f64,
+    /// How far, in px, a ray can see. Positive and finite.
+    pub range: f64,
+    /// One per ray, from the leftmost to the rightmost: how far away the nearest
+    /// thing the ray hits is, as a fraction of `range`. 1 means nothing in range.
+    #[serde(default)]
+    pub readings: Vec<f64>,
+}
+
+/// A [`Lidar`] as written down, before its settings are checked.
+#[derive(Deserialize)]
+struct UncheckedLidar {
+    rays: usize,
+    field_of_view: f64,
+    range: f64,
+    #[serde(default)]
+    readings: Vec<f64>,
+}
+
+impl TryFrom<UncheckedLidar> for Lidar {
+    type Error = LidarError;
+
+    fn try_from(unchecked: UncheckedLidar) -> Result<Self, Self::Error> {
+        let lidar = Lidar {
+            rays: unchecked.rays,
+            field_of_view: unchecked.field_of_view,
+            range: unchecked.range,
+            readings: unchecked.readings,
+        };
+        lidar.validate()?;
+        Ok(lidar)
+    }
+}
+
+/// Why a [`Lidar`] was rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LidarError {
+    NoRays,
+    /// The field of view isn't within 0 and a full turn.
+    InvalidFieldOfView,
+    /// The range isn't a positive, finite number.
+    InvalidRange,
+}
+
+impl std::fmt::Display for LidarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LidarError::NoRays => write!(f, "A lidar needs at least one ray."),
+            LidarError::InvalidFieldOfView => {
+                write!(f, "A lidar's field of view must be within 0 and 2π.")
+            }
+            LidarError::InvalidRange => write!(f, "A lidar's range must be positive."),
+        }
+    }
+}
+
+impl std::error::Error for LidarError {}
+
+impl Default for Lidar {
+    fn default() -> Self {
+        Lidar::new(7, std::f64::consts::TAU / 3., 400.).expect("The default lidar is invalid.")
+    }
+}
+
+impl Lidar {
+    pub fn new(rays: usize, field_of_view: f64, range: f64) -> Result<Self, LidarError> {
+        let lidar = Lidar {
+            rays,
+            field_of_view,
+            range,
+            readings: vec![1.; rays],
+        };
+        lidar.validate()?;
+        Ok(lidar)
+    }
+
+    /// Check the settings make sense. A zero range, say, would make every reading
+    /// NaN.
+    pub fn validate(&self) -> Result<(), LidarError> {
+        if self.rays == 0 {
+            return Err(LidarError::NoRays);
+        }
+        if !(0. ..=std::f64::consts::TAU).contains(&self.field_of_view) {
+            return Err(LidarError::InvalidFieldOfView);
+        }
+        if !self.range.is_finite() || self.range <= 0. {
+            return Err(LidarError::InvalidRange);
+        }
+        Ok(())
+    }
+
+    /// The rays the sensors look along when mounted on `car`, from left to right.
+    /// A single ray looks straight ahead.
+    pub fn rays_from(&self, car: &Car) -> Vec<Ray> {
+        let spacing = if self.rays > 1 {
+            self.field_of_view / (self.rays - 1) as f64
+        } else {
+            0.
+        };
+        let leftmost = car.config.angle + spacing * (self.rays.max(1) - 1) as f64 / 2.;
+        (0..self.rays)
+            .map(|i| Ray::from_angle(car.center(), leftmost - spacing * i as f64))
+            .collect()
+    }
+
+    /// Readings for `car`, given how far along a ray the nearest hit is, if any.
+    pub fn scan(&self, car: &Car, cast: impl Fn(&Ray) -> Option<f64>) -> Vec<f64> {
+        self.rays_from(car)
+            .iter()
+            .map(|ray| {
+                cast(ray)
+                    .map(|distance| (distance / self.range).clamp(0., 1.))
+                    .unwrap_or(1.)
+            })
+            .collect()
+    }
+}
+
+impl Car {
+    pub fn with_lidar(self, lidar: Lidar) -> Self {
+        Car {
+            lidar: Some(lidar),
+            ..self
+        }
+    }
+}
+
+impl Arena {
+    /// Refresh `car`'s [`Lidar`] readings against the walls and obstacles alone.
+    /// Does nothing if it has none.
+    pub fn scan(&self, car: &mut Car) {
+        if let Some(lidar) = &car.lidar {
+            let readings = lidar.scan(car, |ray| {
+                self.raycast(ray, lidar.range).map(|(_, hit)| hit.distance)
+            });
+            car.lidar.as_mut().expect("Checked above.").readings = readings;
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Circle, Corner, World};
+
+    #[test]
+    fn test_rays_fan_out_from_left_to_right() {
+        let car = Car::new(500., 500., 60., 80.)
+            .with_lidar(Lidar::new(3, std::f64::consts::PI, 100.).unwrap());
+        let rays = car.lidar.as_ref().unwrap().rays_from(&car);
+        assert_eq!(rays.len(), 3);
+        // Left, ahead (up the screen) and right.
+        assert!((rays[0].direction.x + 1.).abs() < 1e-9);
+        assert!((rays[1].direction.y + 1.).abs() < 1e-9);
+        assert!((rays[2].direction.x - 1.).abs() < 1e-9);
+
+        let single = Lidar::new(1, 1., 100.).unwrap().rays_from(&car);
+        assert!((single[0].direction.y + 1.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_world_refreshes_readings() {
+        let mut world = World::new(Arena::new(1000., 1000.));
+        world.add_obstacle(Circle::new(Corner { x: 500., y: 300. }, 50.));
+        let id = world.add_car(
+            Car::new(500., 500., 60., 80.)
+                .with_lidar(Lidar::new(3, std::f64::consts::PI, 200.).unwrap()),
+        );
+        world.add_car(Car::new(300., 500., 60., 80.));
+        world.step();
+
+        let readings = &world.car(id).unwrap().lidar.as_ref().unwrap().readings;
+        // The other car 170 px to the left, the obstacle 150 px ahead and nothing
+        // within range to the right.
+        assert!((readings[0] - 170. / 200.).abs() < 1e-9);
+        assert!((readings[1] - 150. / 200.).abs() < 1e-9);
+        assert_eq!(readings[2], 1.);
+
+        let mut car = world.car(id).unwrap().clone();
+        Arena::new(1000., 1000.).scan(&mut car);
+        assert_eq!(car.lidar.unwrap().readings, vec![1.; 3]);
+    }
+
+    #[test]
+    fn test_reject_unusable_lidars() {
+        use std::f64::consts::TAU;
+        assert_eq!(Lidar::new(0, 1., 100.).unwrap_err(), LidarError::NoRays);
+        for range in [0., -100., f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                Lidar::new(3, 1., range).unwrap_err(),
+                LidarError::InvalidRange
+            );
+        }
+        for field_of_view in [-0.1, TAU + 0.1, f64::NAN] {
+            assert_eq!(
+                Lidar::new(3, field_of_view, 100.).unwrap_err(),
+                LidarError::InvalidFieldOfView
+            );
+        }
+        assert!(Lidar::new(3, TAU, 100.).is_ok());
+
+        let parse = |json: &str| serde_json::from_str::<Lidar>(json);
+        assert!(parse(r#"{"rays": 3, "field_of_view": 1, "range": 100}"#).is_ok());
+        let error = parse(r#"{"rays": 3, "field_of_view": 1, "range": 0}"#).unwrap_err();
+        assert!(error.to_string().contains("range"));
+        assert!(parse(r#"{"rays": 0, "field_of_view": 1, "range": 100}"#).is_err());
+    }
+}
@@ -0,0 +1,526 @@
+use crate::{
+    axis_penetration, nearest_hit, polygon_contact, polygon_intersects, Aabb, Contact,
+    ConvexPolygon, Corner, Edge, Ray, RayHit, Rectangle,
+};
+use serde_derive::{Deserialize, Serialize};
+
+/// Below this length a segment is treated as a single point.
+const EPSILON: f64 = 1e-9;
+
+/// Anything that can collide. Implementors only need to describe themselves as a
+/// [`Collider`]; the pairwise tests between every kind of collider live here.
+pub trait Shape {
+    fn collider(&self) -> Collider;
+
+    fn aabb(&self) -> Aabb {
+        self.collider().bounds()
+    }
+
+    fn overlaps<S: Shape + ?Sized>(&self, other: &S) -> bool {
+        overlaps(&self.collider(), &other.collider())
+    }
+
+    /// How `self` and `other` overlap, with the normal pointing towards `other`.
+    fn contact_with<S: Shape + ?Sized>(&self, other: &S) -> Option<Contact> {
+        contact(&self.collider(), &other.collider())
+    }
+
+    /// Where `ray` first meets the shape's outline. A ray starting inside the
+    /// shape hits it on the way out.
+    fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        self.collider().raycast(ray)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Circle {
+    pub center: Corner,
+    pub radius: f64,
+}
+
+impl Circle {
+    pub fn new(center: Corner, radius: f64) -> Self {
+        Circle { center, radius }
+    }
+
+    /// A circle is a capsule whose spine is a single point.
+    pub fn capsule(&self) -> Capsule {
+        Capsule::new(self.center, self.center, self.radius)
+    }
+}
+
+/// Every point within `radius` of the segment from `start` to `end`: a rectangle
+/// with semicircular caps.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Capsule {
+    pub start: Corner,
+    pub end: Corner,
+    pub radius: f64,
+}
+
+impl Capsule {
+    pub fn new(start: Corner, end: Corner, radius: f64) -> Self {
+        Capsule { start, end, radius }
+    }
+
+    fn midpoint(&self) -> Corner {
+        self.start.add(&self.end).scale(0.5)
+    }
+
+    fn is_point(&self) -> bool {
+        self.end.sub(&self.start).length() < EPSILON
+    }
+}
+
+/// A shape as a value, for when the kind of shape is only known at runtime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Collider {
+    Circle(Circle),
+    Capsule(Capsule),
+    Polygon(ConvexPolygon),
+}
+
+fn overlaps(a: &Collider, b: &Collider) -> bool {
+    match (a, b) {
+        (Collider::Polygon(a), Collider::Polygon(b)) => {
+            polygon_intersects(a.vertices(), b.vertices())
+        }
+        _ => contact(a, b).is_some(),
+    }
+}
+
+fn contact(a: &Collider, b: &Collider) -> Option<Contact> {
+    match (a, b) {
+        (Collider::Polygon(a), Collider::Polygon(b)) => polygon_contact(a.vertices(), b.vertices()),
+        (Collider::Polygon(a), b) => capsule_polygon(&b.rounded()?, a).map(Contact::flipped),
+        (a, Collider::Polygon(b)) => capsule_polygon(&a.rounded()?, b),
+        (a, b) => capsule_capsule(&a.rounded()?, &b.rounded()?),
+    }
+}
+
+impl Collider {
+    /// The collider's axis-aligned bounding box.
+    pub fn bounds(&self) -> Aabb {
+        match self {
+            Collider::Circle(circle) => {
+                Aabb::new(circle.center, circle.center).expanded(circle.radius)
+            }
+            Collider::Capsule(capsule) => {
+                Aabb::from_vertices(&[capsule.start, capsule.end]).expanded(capsule.radius)
+            }
+            Collider::Polygon(polygon) => Aabb::from_vertices(polygon.vertices()),
+        }
+    }
+
+    /// Whether `point` lies inside the collider or on its boundary.
+    pub fn contains(&self, point: &Corner) -> bool {
+        match self {
+            Collider::Polygon(polygon) => polygon.contains(point),
+            rounded => {
+                let capsule = rounded.rounded().expect("Only polygons aren't rounded.");
+                let closest = closest_point_on_segment(point, &capsule.start, &capsule.end);
+                point.sub(&closest).length() <= capsule.radius
+            }
+        }
+    }
+
+    /// See [`Shape::raycast`].
+    pub fn raycast(&self, ray: &Ray) -> Option<RayHit> {
+        match self {
+            Collider::Polygon(polygon) => polygon.raycast(ray),
+            rounded => {
+                let capsule = rounded.rounded().expect("Only polygons aren't rounded.");
+                capsule_raycast(&capsule, ray)
+            }
+        }
+    }
+
+    /// Circles and capsules both reduce to a capsule.
+    fn rounded(&self) -> Option<Capsule> {
+        match self {
+            Collider::Circle(circle) => Some(circle.capsule()),
+            Collider::Capsule(capsule) => Some(*capsule),
+            Collider::Polygon(_) => None,
+        }
+    }
+
+    /// Points around the boundary, with round parts approximated by `segments`
+    /// vertices per full turn. Meant for drawing.
+    pub fn outline(&self, segments: usize) -> Vec<Corner> {
+        let arc = |center: Corner, radius: f64, from: f64, turn: f64| {
+            let steps = ((segments as f64) * turn / std::f64::consts::TAU).ceil() as usize;
+            let steps = steps.max(1);
+            (0..=steps)
+                .map(|i| {
+                    let angle = from + turn * i as f64 / steps as f64;
+                    Corner {
+                        x: center.x + radius * angle.cos(),
+                        y: center.y + radius * angle.sin(),
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        match self {
+            Collider::Circle(circle) => {
+                let mut points = arc(circle.center, circle.radius, 0., std::f64::consts::TAU);
+                points.pop();
+                points
+            }
+            Collider::Capsule(capsule) => {
+                let spine = capsule.end.sub(&capsule.start);
+                let facing = spine.y.atan2(spine.x);
+                let half_turn = std::f64::consts::PI;
+                let mut points = arc(
+                    capsule.end,
+                    capsule.radius,
+                    facing - half_turn / 2.,
+                    half_turn,
+                );
+                points.extend(arc(
+                    capsule.start,
+                    capsule.radius,
+                    facing + half_turn / 2.,
+                    half_turn,
+                ));
+                points
+            }
+            Collider::Polygon(polygon) => polygon.vertices().to_vec(),
+        }
+    }
+}
+
+impl Shape for Collider {
+    fn collider(&self) -> Collider {
+        self.clone()
+    }
+}
+
+impl Shape for Circle {
+    fn collider(&self) -> Collider {
+        Collider::Circle(*self)
+    }
+}
+
+impl Shape for Capsule {
+    fn collider(&self) -> Collider {
+        Collider::Capsule(*self)
+    }
+}
+
+impl Shape for ConvexPolygon {
+    fn collider(&self) -> Collider {
+        Collider::Polygon(self.clone())
+    }
+}
+
+impl Shape for Rectangle {
+    fn collider(&self) -> Collider {
+        Collider::Polygon(self.polygon())
+    }
+
+    fn aabb(&self) -> Aabb {
+        Aabb::from_vertices(&self.vertices())
+    }
+}
+
+fn circle_raycast(center: &Corner, radius: f64, ray: &Ray) -> Option<RayHit> {
+    let offset = ray.origin.sub(center);
+    let b = offset.dot(&ray.direction);
+    let c = offset.dot(&offset) - radius * radius;
+    let discriminant = b * b - c;
+    if discriminant < 0. {
+        return None;
+    }
+    let (near, far) = (-b - discriminant.sqrt(), -b + discriminant.sqrt());
+    let distance = if near >= 0. { near } else { far };
+    if distance < 0. {
+        return None;
+    }
+    Some(RayHit::new(ray, distance, ray.at(distance).sub(center)))
+}
+
+/// The nearest hit on either cap or either flat side that actually lies on the
+/// capsule's outline, rather than inside it.
+fn capsule_raycast(capsule: &Capsule, ray: &Ray) -> Option<RayHit> {
+    if capsule.is_point() {
+        return circle_raycast(&capsule.start, capsule.radius, ray);
+    }
+    let spine = capsule.end.sub(&capsule.start).normalized();
+    let offset = Corner {
+        x: -spine.y,
+        y: spine.x,
+    }
+    .scale(capsule.radius);
+    let sides = [offset, offset.scale(-1.)].map(|offset| Edge {
+        start: capsule.start.add(&offset),
+        end: capsule.end.add(&offset),
+    });
+    let hits = [
+        circle_raycast(&capsule.start, capsule.radius, ray),
+        circle_raycast(&capsule.end, capsule.radius, ray),
+        sides[0].raycast(ray),
+        sides[1].raycast(ray),
+    ];
+    nearest_hit(hits.into_iter().map(|hit| {
+        hit.filter(|hit| {
+            let closest = closest_point_on_segment(&hit.point, &capsule.start, &capsule.end);
+            hit.point.sub(&closest).length() >= capsule.radius - 1e-6
+        })
+    }))
+}
+
+fn closest_point_on_segment(point: &Corner, start: &Corner, end: &Corner) -> Corner {
+    let spine = end.sub(start);
+    let length_squared = spine.dot(&spine);
+    if length_squared < EPSILON * EPSILON {
+        return *start;
+    }
+    let t = (point.sub(start).dot(&spine) / length_squared).clamp(0., 1.);
+    start.add(&spine.scale(t))
+}
+
+/// The closest pair of points between segments `p1 q1` and `p2 q2`.
+fn closest_points_between_segments(
+    p1: &Corner,
+    q1: &Corner,
+    p2: &Corner,
+    q2: &Corner,
+) -> (Corner, Corner) {
+    let (d1, d2, r) = (q1.sub(p1), q2.sub(p2), p1.sub(p2));
+    let (a, e, f) = (d1.dot(&d1), d2.dot(&d2), d2.dot(&r));
+    let tiny = EPSILON * EPSILON;
+
+    let (s, t) = if a <= tiny && e <= tiny {
+        (0., 0.)
+    } else if a <= tiny {
+        (0., (f / e).clamp(0., 1.))
+    } else {
+        let c = d1.dot(&r);
+        if e <= tiny {
+            ((-c / a).clamp(0., 1.), 0.)
+        } else {
+            let b = d1.dot(&d2);
+            let denominator = a * e - b * b;
+            let s = if denominator != 0. {
+                ((b * f - c * e) / denominator).clamp(0., 1.)
+            } else {
+                0.
+            };
+            let t = (b * s + f) / e;
+            if t < 0. {
+                ((-c / a).clamp(0., 1.), 0.)
+            } else if t > 1. {
+                (((b - c) / a).clamp(0., 1.), 1.)
+            } else {
+                (s, t)
+            }
+        }
+    };
+    (p1.add(&d1.scale(s)), p2.add(&d2.scale(t)))
+}
+
+fn capsule_capsule(a: &Capsule, b: &Capsule) -> Option<Contact> {
+    let (on_a, on_b) = closest_points_between_segments(&a.start, &a.end, &b.start, &b.end);
+    let between = on_b.sub(&on_a);
+    let distance = between.length();
+    let reach = a.radius + b.radius;
+    if distance > reach {
+        return None;
+    }
+
+    let normal = if distance > EPSILON {
+        between.scale(1. / distance)
+    } else {
+        // The spines cross; push out sideways from `a`'s spine, towards `b`.
+        let spine = a.end.sub(&a.start);
+        let sideways = if a.is_point() {
+            b.midpoint().sub(&a.midpoint())
+        } else {
+            Corner {
+                x: -spine.y,
+                y: spine.x,
+            }
+        };
+        let sideways = if sideways.length() > EPSILON {
+            sideways.normalized()
+        } else {
+            Corner { x: 1., y: 0. }
+        };
+        if sideways.dot(&b.midpoint().sub(&a.midpoint())) < 0. {
+            sideways.scale(-1.)
+        } else {
+            sideways
+        }
+    };
+    let depth = reach - distance;
+
+    Some(Contact {
+        normal,
+        depth,
+        points: vec![on_a.add(&normal.scale(a.radius - depth / 2.))],
+    })
+}
+
+fn capsule_polygon(capsule: &Capsule, polygon: &ConvexPolygon) -> Option<Contact> {
+    let vertices = polygon.vertices();
+    let spine = [capsule.start, capsule.end];
+
+    let spine_inside = if capsule.is_point() {
+        polygon
+            .edges()
+            .iter()
+            .all(|edge| edge.normal().dot(&capsule.start.sub(&edge.start)) <= 0.)
+    } else {
+        polygon_intersects(&spine, vertices)
+    };
+
+    if !spine_inside {
+        // Shallow: the spine is outside, so the contact is wherever the polygon's
+        // boundary comes within `radius` of it.
+        let (on_spine, on_polygon) = polygon
+            .edges()
+            .iter()
+            .map(|edge| {
+                closest_points_between_segments(
+                    &capsule.start,
+                    &capsule.end,
+                    &edge.start,
+                    &edge.end,
+                )
+            })
+            .min_by(|(a1, b1), (a2, b2)| b1.sub(a1).length().total_cmp(&b2.sub(a2).length()))?;
+        let between = on_polygon.sub(&on_spine);
+        let distance = between.length();
+        if distance > capsule.radius || distance < EPSILON {
+            return None;
+        }
+        return Some(Contact {
+            normal: between.scale(1. / distance),
+            depth: capsule.radius - distance,
+            points: vec![on_polygon],
+        });
+    }
+
+    // Deep: the spine itself is inside, so separate along the shallowest axis,
+    // with the capsule's extent widened by its radius.
+    let spine_axis = (!capsule.is_point()).then(|| {
+        let along = capsule.end.sub(&capsule.start);
+        Corner {
+            x: -along.y,
+            y: along.x,
+        }
+    });
+    let (normal, depth) = polygon
+        .edges()
+        .iter()
+        .map(|edge| edge.normal())
+        .chain(spine_axis)
+        .filter_map(|axis| {
+            let axis = axis.normalized();
+            let (min_a, max_a) = axis.project(&spine);
+            let push = axis_penetration(
+                (min_a - capsule.radius, max_a + capsule.radius),
+                axis.project(vertices),
+            )?;
+            Some((axis.scale(push.signum()), push.abs()))
+        })
+        .min_by(|(_, d1), (_, d2)| d1.total_cmp(d2))?;
+    let deepest = if normal.dot(&capsule.start) >= normal.dot(&capsule.end) {
+        capsule.start
+    } else {
+        capsule.end
+    };
+
+    Some(Contact {
+        normal,
+        depth,
+        points: vec![closest_point_on_segment(
+            &deepest,
+            &capsule.start,
+            &capsule.end,
+        )],
+    })
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    fn square() -> ConvexPolygon {
+        Rectangle::new(0., 0., 10., 10., 0.).polygon()
+    }
+
+    #[test]
+    fn test_circle_circle() {
+        let a = Circle::new(Corner { x: 0., y: 0. }, 5.);
+        let b = Circle::new(Corner { x: 8., y: 0. }, 5.);
+        let contact = a.contact_with(&b).unwrap();
+        assert!((contact.normal.x - 1.).abs() < 1e-9);
+        assert!((contact.depth - 2.).abs() < 1e-9);
+        assert!((contact.points[0].x - 4.).abs() < 1e-9);
+        assert!(!a.overlaps(&Circle::new(Corner { x: 11., y: 0. }, 5.)));
+    }
+
+    #[test]
+    fn test_circle_polygon_shallow_and_deep() {
+        let near = Circle::new(Corner { x: 8., y: 0. }, 4.);
+        let contact = square().contact_with(&near).unwrap();
+        assert!((contact.normal.x - 1.).abs() < 1e-9);
+        assert!((contact.depth - 1.).abs() < 1e-9);
+        assert!((contact.points[0].x - 5.).abs() < 1e-9);
+
+        let inside = Circle::new(Corner { x: 3., y: 0. }, 1.);
+        let contact = inside.contact_with(&square()).unwrap();
+        assert!((contact.normal.x + 1.).abs() < 1e-9);
+        assert!((contact.depth - 3.).abs() < 1e-9);
+
+        // Off the corner: the bounding boxes overlap but the circle doesn't reach.
+        assert!(!square().overlaps(&Circle::new(Corner { x: 8., y: 8. }, 4.)));
+    }
+
+    #[test]
+    fn test_capsule_capsule_and_polygon() {
+        let bar = Capsule::new(Corner { x: -10., y: 0. }, Corner { x: 10., y: 0. }, 1.);
+        let post = Capsule::new(Corner { x: 0., y: 1.5 }, Corner { x: 0., y: 10. }, 1.);
+        let contact = bar.contact_with(&post).unwrap();
+        assert!((contact.normal.y - 1.).abs() < 1e-9);
+        assert!((contact.depth - 0.5).abs() < 1e-9);
+
+        let crossing = Capsule::new(Corner { x: 0., y: -5. }, Corner { x: 0., y: 5. }, 1.);
+        assert!(bar.overlaps(&crossing));
+
+        let beside = Capsule::new(Corner { x: 5.5, y: -20. }, Corner { x: 5.5, y: 20. }, 1.);
+        let contact = square().contact_with(&beside).unwrap();
+        assert!((contact.normal.x - 1.).abs() < 1e-9);
+        assert!((contact.depth - 0.5).abs() < 1e-9);
+        assert!(!square().overlaps(&Capsule::new(
+            Corner { x: 7., y: -20. },
+            Corner { x: 7., y: 20. },
+            1.
+        )));
+    }
+
+    #[test]
+    fn test_raycast_rounded_shapes() {
+        let ray = Ray::new(Corner { x: -20., y: 0. }, Corner { x: 1., y: 0. });
+        let hit = Circle::new(Corner { x: 0., y: 0. }, 5.)
+            .raycast(&ray)
+            .unwrap();
+        assert!((hit.distance - 15.).abs() < 1e-9);
+        assert!((hit.normal.x + 1.).abs() < 1e-9);
+
+        // Across the capsule's flat side, and along its spine into a cap.
+        let capsule = Capsule::new(Corner { x: 0., y: -10. }, Corner { x: 0., y: 10. }, 2.);
+        let hit = capsule.raycast(&ray).unwrap();
+        assert!((hit.distance - 18.).abs() < 1e-9);
+        assert!((hit.normal.x + 1.).abs() < 1e-9);
+        let down = Ray::new(Corner { x: 0., y: -30. }, Corner { x: 0., y: 1. });
+        let hit = capsule.raycast(&down).unwrap();
+        assert!((hit.distance - 18.).abs() < 1e-9);
+        assert!((hit.normal.y + 1.).abs() < 1e-9);
+
+        let above = Ray::new(Corner { x: -20., y: 13. }, Corner { x: 1., y: 0. });
+        assert!(capsule.raycast(&above).is_none());
+    }
+}
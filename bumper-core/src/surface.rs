@@ -0,0 +1,144 @@
+use crate::{Collider, Corner, Shape};
+use serde_derive::{Deserialize, Serialize};
+
+/// How a patch of floor changes a car's handling. Each field multiplies the
+/// matching [`CarConfig`](crate::CarConfig) value, so 1 leaves it alone. None may
+/// be negative or non-finite.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Surface {
+    pub friction: f64,
+    pub grip: f64,
+    pub acceleration: f64,
+    pub max_speed: f64,
+}
+
+impl Default for Surface {
+    fn default() -> Self {
+        Surface {
+            friction: 1.,
+            grip: 1.,
+            acceleration: 1.,
+            max_speed: 1.,
+        }
+    }
+}
+
+/// The kinds of floor a zone can be covered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SurfaceKind {
+    /// Hardly slows you down, but you can't get going or stop sliding either.
+    Ice,
+    /// Sticky and slow.
+    Mud,
+    /// Frictionless, with triple the acceleration and a higher top speed for as
+    /// long as you keep your foot down. Coasting across it only keeps you from
+    /// slowing down.
+    BoostPad,
+}
+
+impl SurfaceKind {
+    /// The usual handling on this kind of floor.
+    pub fn surface(&self) -> Surface {
+        match self {
+            SurfaceKind::Ice => Surface {
+                friction: 0.2,
+                grip: 0.1,
+                acceleration: 0.4,
+                max_speed: 1.,
+            },
+            SurfaceKind::Mud => Surface {
+                friction: 4.,
+                grip: 2.,
+                acceleration: 0.5,
+                max_speed: 0.5,
+            },
+            SurfaceKind::BoostPad => Surface {
+                friction: 0.,
+                grip: 1.,
+                acceleration: 3.,
+                max_speed: 1.6,
+            },
+        }
+    }
+}
+
+/// A region of the arena floor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurfaceZone {
+    pub area: Collider,
+    pub kind: SurfaceKind,
+    /// Handling to use instead of the kind's usual one.
+    #[serde(default)]
+    pub surface: Option<Surface>,
+}
+
+impl SurfaceZone {
+    pub fn new<S: Shape>(area: S, kind: SurfaceKind) -> Self {
+        SurfaceZone {
+            area: area.collider(),
+            kind,
+            surface: None,
+        }
+    }
+
+    pub fn with_surface(self, surface: Surface) -> Self {
+        SurfaceZone {
+            surface: Some(surface),
+            ..self
+        }
+    }
+
+    pub fn surface(&self) -> Surface {
+        self.surface.unwrap_or_else(|| self.kind.surface())
+    }
+
+    pub fn contains(&self, point: &Corner) -> bool {
+        self.area.contains(point)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::{Arena, Car, Circle, Rectangle};
+
+    #[test]
+    fn test_ice_keeps_a_car_sliding() {
+        let slide = |surface: Surface| {
+            let mut car = Car::new(100., 100., 60., 80.);
+            car.config.speed = 300.;
+            for _ in 0..30 {
+                car.update_on(1. / 60., &surface);
+            }
+            car.config.speed
+        };
+        assert!(slide(SurfaceKind::Ice.surface()) > slide(Surface::default()));
+        assert!(slide(SurfaceKind::Mud.surface()) < slide(Surface::default()));
+    }
+
+    #[test]
+    fn test_arena_surface_at() {
+        let ice = SurfaceZone::new(Rectangle::new(500., 500., 400., 400., 0.), SurfaceKind::Ice);
+        let pad = SurfaceZone::new(
+            Circle::new(Corner { x: 600., y: 500. }, 50.),
+            SurfaceKind::BoostPad,
+        );
+        let arena = Arena::new(1000., 1000.).with_surface(ice).with_surface(pad);
+
+        assert_eq!(
+            arena.surface_at(&Corner { x: 100., y: 100. }),
+            Surface::default()
+        );
+        assert_eq!(
+            arena.surface_at(&Corner { x: 400., y: 500. }),
+            SurfaceKind::Ice.surface()
+        );
+        // Later zones are laid over earlier ones.
+        assert_eq!(
+            arena.surface_at(&Corner { x: 620., y: 500. }),
+            SurfaceKind::BoostPad.surface()
+        );
+    }
+}
@@ -0,0 +1,647 @@
+//! Import of levels drawn in the [Tiled](https://www.mapeditor.org/) map editor,
+//! saved in its JSON (`.tmj`) or XML (`.tmx`) format. Both formats hold the same
+//! things under the same names, so they're read into the same structures.
+//!
+//! Only object layers are read; tile layers and images are left to the client.
+//! What an object becomes is decided by its class (called "type" before Tiled 1.9),
+//! falling back to the class and then the name of the layer it's on, so a layer
+//! called "Spawns" full of points works without tagging every point:
+//!
+//! * `spawn`: a point where cars start. Its rotation is the way they face.
+//! * `wall`: a polyline (or polygon) whose every segment becomes a [`Wall`]. The
+//!   `thickness` property sets how thick, in px.
+//! * `ice`, `mud` or `boost_pad`: a [`SurfaceZone`] of that kind.
+//! * `objective`: an [`Objective`] called by the object's name.
+//! * `obstacle`, or anything else: a solid obstacle.
+//!
+//! Rectangles, ellipses and convex polygons become shapes; ellipses are read as
+//! circles, taking the mean of their width and height as the diameter.
+
+use crate::xml::{self, Element};
+use crate::{
+    Circle, Collider, ConvexPolygon, Corner, Map, MapError, Objective, Spawn, SurfaceKind,
+    SurfaceZone, Wall,
+};
+use serde_derive::Deserialize;
+
+/// How thick, in px, walls are when their object doesn't say.
+pub const DEFAULT_TILED_WALL_THICKNESS: f64 = 20.;
+
+/// Why a Tiled map couldn't be imported. Objects are identified by their Tiled id.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TiledError {
+    /// The file isn't valid JSON or XML, or isn't a Tiled map.
+    Parse(String),
+    /// The object is a kind of shape that can't play the part asked of it, like a
+    /// rectangle tagged as a spawn point.
+    Unsupported { object: u32, reason: &'static str },
+    /// The object is a concave polygon, which can't be an obstacle or zone.
+    Concave { object: u32 },
+    /// The imported map failed validation.
+    Map(MapError),
+}
+
+impl std::fmt::Display for TiledError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TiledError::Parse(message) => write!(f, "Couldn't parse Tiled map: {}", message),
+            TiledError::Unsupported { object, reason } => {
+                write!(f, "Object {} can't be imported: {}.", object, reason)
+            }
+            TiledError::Concave { object } => {
+                write!(
+                    f,
+                    "Object {} is a concave polygon; split it into convex ones.",
+                    object
+                )
+            }
+            TiledError::Map(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TiledError {}
+
+impl From<MapError> for TiledError {
+    fn from(error: MapError) -> Self {
+        TiledError::Map(error)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct TiledMap {
+    width: f64,
+    height: f64,
+    #[serde(rename = "tilewidth")]
+    tile_width: f64,
+    #[serde(rename = "tileheight")]
+    tile_height: f64,
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TiledLayer {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    class: String,
+    #[serde(default, rename = "offsetx")]
+    offset_x: f64,
+    #[serde(default, rename = "offsety")]
+    offset_y: f64,
+    /// Object layers only.
+    #[serde(default)]
+    objects: Vec<TiledObject>,
+    /// Group layers only.
+    #[serde(default)]
+    layers: Vec<TiledLayer>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TiledObject {
+    id: u32,
+    #[serde(default)]
+    name: String,
+    /// Called `class` since Tiled 1.9.
+    #[serde(default, alias = "class")]
+    r#type: String,
+    x: f64,
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    /// Degrees clockwise about `(x, y)`.
+    #[serde(default)]
+    rotation: f64,
+    #[serde(default)]
+    point: bool,
+    #[serde(default)]
+    ellipse: bool,
+    polygon: Option<Vec<Corner>>,
+    polyline: Option<Vec<Corner>>,
+    #[serde(default)]
+    properties: Vec<TiledProperty>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TiledProperty {
+    name: String,
+    value: serde_json::Value,
+}
+
+fn number_property(properties: &[TiledProperty], name: &str) -> Option<f64> {
+    properties
+        .iter()
+        .find(|property| property.name == name)
+        .and_then(|property| property.value.as_f64())
+}
+
+/// What an object is for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Role {
+    Spawn,
+    Wall,
+    Surface(SurfaceKind),
+    Objective,
+    Obstacle,
+}
+
+impl Role {
+    /// Read a role from a class or layer name, forgiving case and plurals.
+    fn parse(name: &str) -> Option<Role> {
+        match name.trim().to_lowercase().replace([' ', '-'], "_").as_str() {
+            "spawn" | "spawns" | "spawn_point" | "spawn_points" => Some(Role::Spawn),
+            "wall" | "walls" => Some(Role::Wall),
+            "ice" => Some(Role::Surface(SurfaceKind::Ice)),
+            "mud" => Some(Role::Surface(SurfaceKind::Mud)),
+            "boost_pad" | "boost_pads" => Some(Role::Surface(SurfaceKind::BoostPad)),
+            "objective" | "objectives" => Some(Role::Objective),
+            "obstacle" | "obstacles" => Some(Role::Obstacle),
+            _ => None,
+        }
+    }
+}
+
+impl TiledObject {
+    /// A point relative to the object's origin, in world coordinates.
+    fn place(&self, offset: &Corner, point: &Corner) -> Corner {
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        Corner {
+            x: offset.x + self.x + point.x * cos - point.y * sin,
+            y: offset.y + self.y + point.x * sin + point.y * cos,
+        }
+    }
+
+    /// The object's outline, for rectangles and polygons.
+    fn corners(&self, offset: &Corner) -> Option<Vec<Corner>> {
+        let local = match &self.polygon {
+            Some(polygon) => polygon.clone(),
+            None if self.point || self.ellipse || self.polyline.is_some() => return None,
+            None => vec![
+                Corner { x: 0., y: 0. },
+                Corner {
+                    x: self.width,
+                    y: 0.,
+                },
+                Corner {
+                    x: self.width,
+                    y: self.height,
+                },
+                Corner {
+                    x: 0.,
+                    y: self.height,
+                },
+            ],
+        };
+        Some(
+            local
+                .iter()
+                .map(|point| self.place(offset, point))
+                .collect(),
+        )
+    }
+
+    /// The object as a solid shape.
+    fn area(&self, offset: &Corner) -> Result<Collider, TiledError> {
+        if self.ellipse {
+            let center = self.place(
+                offset,
+                &Corner {
+                    x: self.width / 2.,
+                    y: self.height / 2.,
+                },
+            );
+            return Ok(Collider::Circle(Circle::new(
+                center,
+                (self.width + self.height) / 4.,
+            )));
+        }
+        let corners = self.corners(offset).ok_or(TiledError::Unsupported {
+            object: self.id,
+            reason: "points and polylines have no area",
+        })?;
+        let polygon = ConvexPolygon::new(corners);
+        if !polygon.is_convex() {
+            return Err(TiledError::Concave { object: self.id });
+        }
+        Ok(Collider::Polygon(polygon))
+    }
+
+    fn walls(&self, offset: &Corner) -> Result<Vec<Wall>, TiledError> {
+        let thickness =
+            number_property(&self.properties, "thickness").unwrap_or(DEFAULT_TILED_WALL_THICKNESS);
+        let (points, closed) = match (&self.polyline, &self.polygon) {
+            (Some(points), _) => (points, false),
+            (None, Some(points)) => (points, true),
+            _ => {
+                return Err(TiledError::Unsupported {
+                    object: self.id,
+                    reason: "walls must be polylines or polygons",
+                })
+            }
+        };
+        let points = points
+            .iter()
+            .map(|point| self.place(offset, point))
+            .collect::<Vec<_>>();
+        let segments = if closed {
+            points.len()
+        } else {
+            points.len().saturating_sub(1)
+        };
+        Ok((0..segments)
+            .map(|i| Wall::new(points[i], points[(i + 1) % points.len()], thickness))
+            .collect())
+    }
+}
+
+impl Map {
+    /// Import a map saved by Tiled in its JSON format, and validate it.
+    pub fn from_tiled_json(json: &str) -> Result<Map, TiledError> {
+        let tiled: TiledMap =
+            serde_json::from_str(json).map_err(|e| TiledError::Parse(e.to_string()))?;
+        tiled.import()
+    }
+
+    /// Import a map saved by Tiled in its XML format, and validate it.
+    pub fn from_tiled_xml(xml: &str) -> Result<Map, TiledError> {
+        let root = xml::parse(xml).map_err(TiledError::Parse)?;
+        TiledMap::from_xml(&root)?.import()
+    }
+}
+
+impl TiledMap {
+    /// Build the map this describes, and validate it.
+    fn import(&self) -> Result<Map, TiledError> {
+        let mut map = Map {
+            name: self
+                .properties
+                .iter()
+                .find(|property| property.name == "name")
+                .and_then(|property| property.value.as_str())
+                .unwrap_or_default()
+                .to_string(),
+            width: self.width * self.tile_width,
+            height: self.height * self.tile_height,
+            walls: Vec::new(),
+            obstacles: Vec::new(),
+            spawns: Vec::new(),
+            surfaces: Vec::new(),
+            objectives: Vec::new(),
+            restitution: number_property(&self.properties, "restitution"),
+        };
+        for layer in &self.layers {
+            import_layer(&mut map, layer, Corner { x: 0., y: 0. }, None)?;
+        }
+        map.validate()?;
+        Ok(map)
+    }
+}
+
+// In the XML format, what the JSON format has as a field is an attribute or child
+// element of the same name.
+
+fn xml_error(element: &Element, what: &str) -> TiledError {
+    TiledError::Parse(format!("<{}> {}", element.name, what))
+}
+
+fn xml_number(element: &Element, name: &str) -> Result<Option<f64>, TiledError> {
+    element
+        .attribute(name)
+        .map(|value| {
+            value.trim().parse().map_err(|_| {
+                xml_error(
+                    element,
+                    &format!("has a bad {} attribute: {:?}", name, value),
+                )
+            })
+        })
+        .transpose()
+}
+
+fn xml_required(element: &Element, name: &str) -> Result<f64, TiledError> {
+    xml_number(element, name)?
+        .ok_or_else(|| xml_error(element, &format!("has no {} attribute", name)))
+}
+
+/// A `points` attribute, like `"0,0 60,0 30,40"`.
+fn xml_points(element: &Element) -> Result<Vec<Corner>, TiledError> {
+    let points = element.attribute("points").unwrap_or_default();
+    points
+        .split_whitespace()
+        .map(|pair| {
+            let (x, y) = pair.split_once(',').unwrap_or((pair, ""));
+            match (x.parse(), y.parse()) {
+                (Ok(x), Ok(y)) => Ok(Corner { x, y }),
+                _ => Err(xml_error(element, &format!("has a bad point: {:?}", pair))),
+            }
+        })
+        .collect()
+}
+
+fn xml_properties(element: &Element) -> Result<Vec<TiledProperty>, TiledError> {
+    element
+        .child("properties")
+        .map_or(&[][..], |properties| &properties.children)
+        .iter()
+        .filter(|property| property.name == "property")
+        .map(|property| {
+            let name = property
+                .attribute("name")
+                .ok_or_else(|| xml_error(property, "has no name attribute"))?;
+            let raw = property.attribute("value").unwrap_or_default();
+            let value = match property.attribute("type") {
+                Some("int" | "float") => serde_json::Value::from(xml_required(property, "value")?),
+                Some("bool") => serde_json::Value::Bool(raw == "true"),
+                _ => serde_json::Value::from(raw),
+            };
+            Ok(TiledProperty {
+                name: name.to_string(),
+                value,
+            })
+        })
+        .collect()
+}
+
+/// The layers directly inside `parent`, in order.
+fn xml_layers(parent: &Element) -> Result<Vec<TiledLayer>, TiledError> {
+    parent
+        .children
+        .iter()
+        .filter(|child| {
+            matches!(
+                child.name.as_str(),
+                "layer" | "objectgroup" | "imagelayer" | "group"
+            )
+        })
+        .map(TiledLayer::from_xml)
+        .collect()
+}
+
+impl TiledMap {
+    fn from_xml(root: &Element) -> Result<TiledMap, TiledError> {
+        if root.name != "map" {
+            return Err(xml_error(root, "isn't a Tiled map"));
+        }
+        Ok(TiledMap {
+            width: xml_required(root, "width")?,
+            height: xml_required(root, "height")?,
+            tile_width: xml_required(root, "tilewidth")?,
+            tile_height: xml_required(root, "tileheight")?,
+            layers: xml_layers(root)?,
+            properties: xml_properties(root)?,
+        })
+    }
+}
+
+impl TiledLayer {
+    fn from_xml(layer: &Element) -> Result<TiledLayer, TiledError> {
+        Ok(TiledLayer {
+            // Tile layers are `<layer>`s in XML but "tilelayer"s in JSON.
+            kind: match layer.name.as_str() {
+                "layer" => "tilelayer".to_string(),
+                kind => kind.to_string(),
+            },
+            name: layer.attribute("name").unwrap_or_default().to_string(),
+            class: layer.attribute("class").unwrap_or_default().to_string(),
+            offset_x: xml_number(layer, "offsetx")?.unwrap_or_default(),
+            offset_y: xml_number(layer, "offsety")?.unwrap_or_default(),
+            objects: layer
+                .children_named("object")
+                .map(TiledObject::from_xml)
+                .collect::<Result<_, _>>()?,
+            layers: xml_layers(layer)?,
+        })
+    }
+}
+
+impl TiledObject {
+    fn from_xml(object: &Element) -> Result<TiledObject, TiledError> {
+        let id = object
+            .attribute("id")
+            .and_then(|id| id.parse().ok())
+            .ok_or_else(|| xml_error(object, "has no valid id"))?;
+        Ok(TiledObject {
+            id,
+            name: object.attribute("name").unwrap_or_default().to_string(),
+            r#type: object
+                .attribute("class")
+                .or_else(|| object.attribute("type"))
+                .unwrap_or_default()
+                .to_string(),
+            x: xml_number(object, "x")?.unwrap_or_default(),
+            y: xml_number(object, "y")?.unwrap_or_default(),
+            width: xml_number(object, "width")?.unwrap_or_default(),
+            height: xml_number(object, "height")?.unwrap_or_default(),
+            rotation: xml_number(object, "rotation")?.unwrap_or_default(),
+            point: object.child("point").is_some(),
+            ellipse: object.child("ellipse").is_some(),
+            polygon: object.child("polygon").map(xml_points).transpose()?,
+            polyline: object.child("polyline").map(xml_points).transpose()?,
+            properties: xml_properties(object)?,
+        })
+    }
+}
+
+fn import_layer(
+    map: &mut Map,
+    layer: &TiledLayer,
+    offset: Corner,
+    inherited: Option<Role>,
+) -> Result<(), TiledError> {
+    let offset = offset.add(&Corner {
+        x: layer.offset_x,
+        y: layer.offset_y,
+    });
+    let layer_role = Role::parse(&layer.class)
+        .or_else(|| Role::parse(&layer.name))
+        .or(inherited);
+    match layer.kind.as_str() {
+        "group" => {
+            for child in &layer.layers {
+                import_layer(map, child, offset, layer_role)?;
+            }
+        }
+        "objectgroup" => {
+            for object in &layer.objects {
+                let role = Role::parse(&object.r#type)
+                    .or(layer_role)
+                    .unwrap_or(Role::Obstacle);
+                import_object(map, object, &offset, role)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn import_object(
+    map: &mut Map,
+    object: &TiledObject,
+    offset: &Corner,
+    role: Role,
+) -> Result<(), TiledError> {
+    match role {
+        Role::Spawn => {
+            if !object.point {
+                return Err(TiledError::Unsupported {
+                    object: object.id,
+                    reason: "spawn points must be points",
+                });
+            }
+            map.spawns.push(Spawn {
+                position: object.place(offset, &Corner { x: 0., y: 0. }),
+                // Tiled turns clockwise from facing up; `angle` turns the other way.
+                angle: (-object.rotation.to_radians()).rem_euclid(std::f64::consts::TAU),
+            });
+        }
+        Role::Wall => map.walls.extend(object.walls(offset)?),
+        Role::Surface(kind) => map
+            .surfaces
+            .push(SurfaceZone::new(object.area(offset)?, kind)),
+        Role::Objective => map.objectives.push(Objective {
+            name: object.name.clone(),
+            area: object.area(offset)?,
+        }),
+        Role::Obstacle => map.obstacles.push(object.area(offset)?),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::Car;
+
+    const LEVEL: &str = r#"{
+        "type": "map", "orientation": "orthogonal", "infinite": false,
+        "width": 20, "height": 10, "tilewidth": 32, "tileheight": 32,
+        "properties": [{"name": "name", "type": "string", "value": "Test"}],
+        "layers": [
+            {"type": "tilelayer", "name": "Floor", "data": []},
+            {"type": "objectgroup", "name": "Spawns", "objects": [
+                {"id": 1, "x": 64, "y": 64, "point": true, "rotation": 90},
+                {"id": 2, "x": 576, "y": 256, "point": true}
+            ]},
+            {"type": "group", "name": "Scenery", "offsetx": 10, "layers": [
+                {"type": "objectgroup", "name": "Obstacles", "objects": [
+                    {"id": 3, "x": 300, "y": 100, "width": 40, "height": 20},
+                    {"id": 4, "x": 200, "y": 200, "width": 30, "height": 30, "ellipse": true},
+                    {"id": 5, "class": "wall", "x": 100, "y": 300, "polyline": [
+                        {"x": 0, "y": 0}, {"x": 100, "y": 0}, {"x": 100, "y": -50}
+                    ], "properties": [{"name": "thickness", "type": "float", "value": 8}]},
+                    {"id": 6, "type": "ice", "x": 400, "y": 50, "polygon": [
+                        {"x": 0, "y": 0}, {"x": 60, "y": 0}, {"x": 30, "y": 40}
+                    ]},
+                    {"id": 7, "class": "objective", "name": "hill", "x": 450, "y": 150,
+                     "width": 50, "height": 50, "rotation": 45}
+                ]}
+            ]}
+        ]
+    }"#;
+
+    /// [`LEVEL`] as Tiled saves it in XML.
+    const LEVEL_TMX: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+        <map version="1.10" orientation="orthogonal" width="20" height="10"
+             tilewidth="32" tileheight="32" infinite="0">
+         <properties>
+          <property name="name" value="Test"/>
+         </properties>
+         <layer id="1" name="Floor" width="20" height="10">
+          <data encoding="csv">0,0,0</data>
+         </layer>
+         <objectgroup id="2" name="Spawns">
+          <object id="1" x="64" y="64" rotation="90"><point/></object>
+          <object id="2" x="576" y="256"><point/></object>
+         </objectgroup>
+         <group id="3" name="Scenery" offsetx="10">
+          <objectgroup id="4" name="Obstacles">
+           <object id="3" x="300" y="100" width="40" height="20"/>
+           <object id="4" x="200" y="200" width="30" height="30"><ellipse/></object>
+           <object id="5" class="wall" x="100" y="300">
+            <properties><property name="thickness" type="float" value="8"/></properties>
+            <polyline points="0,0 100,0 100,-50"/>
+           </object>
+           <object id="6" type="ice" x="400" y="50"><polygon points="0,0 60,0 30,40"/></object>
+           <object id="7" name="hill" class="objective" x="450" y="150"
+                   width="50" height="50" rotation="45"/>
+          </objectgroup>
+         </group>
+        </map>"#;
+
+    #[test]
+    fn test_import_object_layers() {
+        let map = Map::from_tiled_json(LEVEL).unwrap();
+        assert_eq!(map.name, "Test");
+        assert_eq!((map.width, map.height), (640., 320.));
+
+        assert_eq!(map.spawns.len(), 2);
+        // Rotated a quarter turn clockwise from facing up, so facing right.
+        let car = Car::new(0., 0., 60., 80.).with_angle(map.spawns[0].angle);
+        assert!((car.heading().x - 1.).abs() < 1e-9);
+
+        assert_eq!(map.obstacles.len(), 2);
+        let bounds = map.obstacles[0].bounds();
+        assert!((bounds.min.x - 310.).abs() < 1e-9 && (bounds.max.y - 120.).abs() < 1e-9);
+        assert!(matches!(map.obstacles[1], Collider::Circle(circle) if circle.radius == 15.));
+
+        assert_eq!(map.walls.len(), 2);
+        assert_eq!(map.walls[0].thickness, 8.);
+        assert_eq!(map.surfaces[0].kind, SurfaceKind::Ice);
+        assert!(map.objective("hill").is_some());
+    }
+
+    #[test]
+    fn test_xml_imports_like_json() {
+        let from_xml = Map::from_tiled_xml(LEVEL_TMX).unwrap();
+        let from_json = Map::from_tiled_json(LEVEL).unwrap();
+        assert_eq!(
+            serde_json::to_value(&from_xml).unwrap(),
+            serde_json::to_value(&from_json).unwrap()
+        );
+
+        let bad_point = LEVEL_TMX.replace("60,0 30,40", "60,0 30");
+        assert!(matches!(
+            Map::from_tiled_xml(&bad_point).unwrap_err(),
+            TiledError::Parse(_)
+        ));
+        assert!(matches!(
+            Map::from_tiled_xml("<tileset/>").unwrap_err(),
+            TiledError::Parse(_)
+        ));
+
+        assert_eq!(Role::parse("Spawn Points"), Some(Role::Spawn));
+        assert_eq!(
+            Role::parse("boost-pads"),
+            Some(Role::Surface(SurfaceKind::BoostPad))
+        );
+        assert_eq!(Role::parse("ices"), None);
+    }
+
+    #[test]
+    fn test_reject_unusable_objects() {
+        let concave = LEVEL.replace(
+            r#"{"x": 0, "y": 0}, {"x": 60, "y": 0}, {"x": 30, "y": 40}"#,
+            r#"{"x": 0, "y": 0}, {"x": 60, "y": 0}, {"x": 30, "y": 10}, {"x": 30, "y": 40}"#,
+        );
+        assert_eq!(
+            Map::from_tiled_json(&concave).unwrap_err(),
+            TiledError::Concave { object: 6 }
+        );
+
+        let boxed_spawn = LEVEL.replace(
+            r#""x": 576, "y": 256, "point": true"#,
+            r#""x": 576, "y": 256, "width": 10, "height": 10"#,
+        );
+        assert!(matches!(
+            Map::from_tiled_json(&boxed_spawn).unwrap_err(),
+            TiledError::Unsupported { object: 2, .. }
+        ));
+    }
+}
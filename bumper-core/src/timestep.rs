@@ -0,0 +1,89 @@
+use crate::FRAME_DT;
+
+/// Accumulates wall-clock time and hands it back out in fixed-size steps, so a
+/// simulation advances identically whatever the caller's frame rate is.
+#[derive(Debug, Clone, Copy)]
+pub struct FixedTimestep {
+    /// Length of a single step, in seconds.
+    pub dt: f64,
+    /// The most steps a single call to [`FixedTimestep::advance`] will hand out.
+    /// Anything beyond that is dropped so a long stall can't snowball.
+    pub max_steps: usize,
+    accumulator: f64,
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        FixedTimestep::new(FRAME_DT)
+    }
+}
+
+impl FixedTimestep {
+    pub fn new(dt: f64) -> Self {
+        FixedTimestep {
+            dt,
+            max_steps: 8,
+            accumulator: 0.,
+        }
+    }
+
+    pub fn with_max_steps(self, max_steps: usize) -> Self {
+        FixedTimestep { max_steps, ..self }
+    }
+
+    /// Add `elapsed` seconds to the accumulator and return how many steps of
+    /// `dt` should be run now. A `dt` that isn't positive never steps.
+    pub fn advance(&mut self, elapsed: f64) -> usize {
+        if self.dt.is_nan() || self.dt <= 0. {
+            return 0;
+        }
+        if elapsed.is_finite() && elapsed > 0. {
+            self.accumulator += elapsed;
+        }
+        let mut steps = 0;
+        while self.accumulator >= self.dt && steps < self.max_steps {
+            self.accumulator -= self.dt;
+            steps += 1;
+        }
+        if steps == self.max_steps {
+            // Keep only the partial step, so `alpha` stays below 1.
+            self.accumulator %= self.dt;
+        }
+        steps
+    }
+
+    /// How far, in `[0, 1)`, the leftover time is into the next step. Useful to
+    /// interpolate rendering between the last two simulated states.
+    pub fn alpha(&self) -> f64 {
+        if self.dt.is_nan() || self.dt <= 0. {
+            return 0.;
+        }
+        (self.accumulator / self.dt).clamp(0., 1.)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_advance_accumulates_partial_steps() {
+        let mut timestep = FixedTimestep::new(0.1);
+        assert_eq!(timestep.advance(0.05), 0);
+        assert_eq!(timestep.advance(0.07), 1);
+        assert!((timestep.alpha() - 0.2).abs() < 1e-9);
+        assert_eq!(timestep.advance(10.), timestep.max_steps);
+    }
+
+    #[test]
+    fn test_alpha_stays_below_one() {
+        let mut timestep = FixedTimestep::new(0.1).with_max_steps(2);
+        assert_eq!(timestep.advance(1.05), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-9);
+        assert_eq!(timestep.advance(0.), 0);
+
+        let mut stopped = FixedTimestep::new(0.);
+        assert_eq!(stopped.advance(1.), 0);
+        assert_eq!(stopped.alpha(), 0.);
+    }
+}
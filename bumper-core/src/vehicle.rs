@@ -0,0 +1,210 @@
+use crate::{Car, CarConfig, CarConfigError};
+use serde_derive::{Deserialize, Serialize};
+
+/// The presets that ship with the game.
+const DEFAULT_VEHICLES: &str = include_str!("../vehicles.json");
+
+/// A kind of car a player can pick, like a heavy tank or a nimble buggy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VehicleClass {
+    /// What players ask for the class by. Unique within [`VehicleClasses`].
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub width: f64,
+    pub height: f64,
+    /// Handling of the class. Anything left out takes its value from
+    /// [`CarConfig::default`].
+    #[serde(default)]
+    pub config: CarConfig,
+}
+
+impl VehicleClass {
+    /// A fresh car of this class centred at `(x, y)`, standing still with a full
+    /// boost meter.
+    pub fn car(&self, x: f64, y: f64) -> Car {
+        Car::new(x, y, self.width, self.height).with_config(self.fresh_config())
+    }
+
+    fn fresh_config(&self) -> CarConfig {
+        CarConfig {
+            speed: 0.,
+            lateral_speed: 0.,
+            angular_speed: 0.,
+            ..self.config
+        }
+    }
+
+    fn validate(&self) -> Result<(), VehicleError> {
+        if self.name.trim().is_empty() {
+            return Err(VehicleError::UnnamedClass);
+        }
+        let positive = |value: f64| value > 0. && value.is_finite();
+        if !positive(self.width) || !positive(self.height) {
+            return Err(VehicleError::InvalidSize {
+                class: self.name.clone(),
+            });
+        }
+        self.fresh_config()
+            .validate()
+            .map_err(|error| VehicleError::InvalidConfig {
+                class: self.name.clone(),
+                error,
+            })
+    }
+}
+
+/// Why a set of vehicle classes was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VehicleError {
+    /// The file isn't valid JSON, or doesn't have the expected shape.
+    Parse(String),
+    NoClasses,
+    UnnamedClass,
+    DuplicateClass(String),
+    /// A class has a non-positive or non-finite width or height.
+    InvalidSize {
+        class: String,
+    },
+    /// A class has a handling parameter out of range.
+    InvalidConfig {
+        class: String,
+        error: CarConfigError,
+    },
+}
+
+impl std::fmt::Display for VehicleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VehicleError::Parse(message) => {
+                write!(f, "Couldn't parse vehicle classes: {}", message)
+            }
+            VehicleError::NoClasses => write!(f, "There are no vehicle classes."),
+            VehicleError::UnnamedClass => write!(f, "A vehicle class has no name."),
+            VehicleError::DuplicateClass(name) => {
+                write!(f, "Vehicle class {:?} is defined more than once.", name)
+            }
+            VehicleError::InvalidSize { class } => {
+                write!(f, "Vehicle class {:?} has an invalid size.", class)
+            }
+            VehicleError::InvalidConfig { class, error } => {
+                write!(
+                    f,
+                    "Vehicle class {:?} has an invalid config: {}",
+                    class, error
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for VehicleError {}
+
+/// Every vehicle class on offer. The first one is what players get when they
+/// don't pick one, or pick one that doesn't exist.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VehicleClasses {
+    classes: Vec<VehicleClass>,
+}
+
+impl Default for VehicleClasses {
+    fn default() -> Self {
+        VehicleClasses::from_json(DEFAULT_VEHICLES)
+            .expect("The built-in vehicle classes are invalid.")
+    }
+}
+
+impl VehicleClasses {
+    pub fn new(classes: Vec<VehicleClass>) -> Result<Self, VehicleError> {
+        let vehicles = VehicleClasses { classes };
+        vehicles.validate()?;
+        Ok(vehicles)
+    }
+
+    /// Load and validate classes from JSON of the form `{"classes": [...]}`.
+    pub fn from_json(json: &str) -> Result<Self, VehicleError> {
+        let vehicles: VehicleClasses =
+            serde_json::from_str(json).map_err(|e| VehicleError::Parse(e.to_string()))?;
+        vehicles.validate()?;
+        Ok(vehicles)
+    }
+
+    pub fn validate(&self) -> Result<(), VehicleError> {
+        if self.classes.is_empty() {
+            return Err(VehicleError::NoClasses);
+        }
+        for (index, class) in self.classes.iter().enumerate() {
+            class.validate()?;
+            if self.classes[..index]
+                .iter()
+                .any(|other| other.name == class.name)
+            {
+                return Err(VehicleError::DuplicateClass(class.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&VehicleClass> {
+        self.classes.iter().find(|class| class.name == name)
+    }
+
+    /// The class called `name`, or the default one if there's no such class or no
+    /// name was given.
+    pub fn get_or_default(&self, name: Option<&str>) -> &VehicleClass {
+        name.and_then(|name| self.get(name))
+            .unwrap_or_else(|| self.default_class())
+    }
+
+    pub fn default_class(&self) -> &VehicleClass {
+        &self.classes[0]
+    }
+
+    pub fn classes(&self) -> &[VehicleClass] {
+        &self.classes
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_built_in_classes_are_valid() {
+        let vehicles = VehicleClasses::default();
+        assert_eq!(vehicles.default_class().name, "standard");
+        let tank = vehicles.get_or_default(Some("tank")).car(100., 100.);
+        let buggy = vehicles.get_or_default(Some("buggy")).car(100., 100.);
+        assert!(tank.config.mass > buggy.config.mass);
+        assert!(tank.width > buggy.width);
+        assert_eq!(tank.boost, tank.config.boost_capacity);
+        assert_eq!(vehicles.get_or_default(Some("hovercraft")).name, "standard");
+    }
+
+    #[test]
+    fn test_invalid_classes_are_rejected() {
+        let load =
+            |classes: &str| VehicleClasses::from_json(&format!("{{\"classes\": {}}}", classes));
+        assert_eq!(load("[]").unwrap_err(), VehicleError::NoClasses);
+        assert!(matches!(load("{}").unwrap_err(), VehicleError::Parse(_)));
+        assert_eq!(
+            load(r#"[{"name": "a", "width": 1, "height": 1}, {"name": "a", "width": 2, "height": 2}]"#)
+                .unwrap_err(),
+            VehicleError::DuplicateClass("a".to_string())
+        );
+        assert_eq!(
+            load(r#"[{"name": "flat", "width": 60, "height": 0}]"#).unwrap_err(),
+            VehicleError::InvalidSize {
+                class: "flat".to_string()
+            }
+        );
+        assert_eq!(
+            load(r#"[{"name": "slug", "width": 60, "height": 80, "config": {"max_speed": 0}}]"#)
+                .unwrap_err(),
+            VehicleError::InvalidConfig {
+                class: "slug".to_string(),
+                error: CarConfigError::NotPositive { field: "max_speed" }
+            }
+        );
+    }
+}
@@ -0,0 +1,457 @@
+use crate::{
+    could_tunnel, interpolate, mutual_time_of_impact, swept_hitbox, Arena, BroadPhase, Car,
+    Control, Corner, FixedTimestep, Impact, Ray, RayHit, Shape, Side,
+};
+use serde_derive::{Deserialize, Serialize};
+
+/// Identifies a car for as long as it is in a [`World`]. Ids are never reused.
+pub type CarId = u32;
+
+/// Something that happened during a [`World::step`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum WorldEvent {
+    CarAdded { car: CarId },
+    CarRemoved { car: CarId },
+    Collision(CollisionEvent),
+}
+
+/// What a car ran into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Struck {
+    /// Another car, and the side of it that was hit.
+    Car { car: CarId, side: Side },
+    /// One of [`Arena::walls`].
+    Wall { index: usize },
+    /// One of [`Arena::obstacles`].
+    Obstacle { index: usize },
+}
+
+impl Struck {
+    /// What the solid at `index`, in [`Arena::solids`] order, is.
+    fn solid(index: usize, walls: usize) -> Self {
+        if index < walls {
+            Struck::Wall { index }
+        } else {
+            Struck::Obstacle {
+                index: index - walls,
+            }
+        }
+    }
+}
+
+/// A car ran into something.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct CollisionEvent {
+    pub car: CarId,
+    /// The side of `car` that was hit.
+    pub side: Side,
+    pub other: Struck,
+    /// Magnitude of the impulse that pushed them apart. Zero for a touch between
+    /// things already moving apart.
+    pub impulse: f64,
+    /// Where they touched, in world coordinates.
+    pub point: Corner,
+    /// Unit normal pointing from `car` towards `other`.
+    pub normal: Corner,
+    /// Velocity of `other` relative to `car` at `point`, just before the impact.
+    pub relative_velocity: Corner,
+}
+
+impl CollisionEvent {
+    pub fn new(id: CarId, car: &Car, other: Struck, impact: Impact) -> Self {
+        CollisionEvent {
+            car: id,
+            side: car.side_of(&impact.point),
+            other,
+            impulse: impact.impulse,
+            point: impact.point,
+            normal: impact.normal,
+            relative_velocity: impact.relative_velocity,
+        }
+    }
+
+    /// Whether `id` took part in the collision, on either end.
+    pub fn involves(&self, id: CarId) -> bool {
+        self.car == id || matches!(self.other, Struck::Car { car, .. } if car == id)
+    }
+}
+
+/// The whole simulation: the arena with its walls and obstacles, and the cars
+/// driving around in it. Everything that simulates the game (the browser, the
+/// server, bots and tests) steps one of these, so they all agree.
+#[derive(Debug, Clone)]
+pub struct World {
+    pub arena: Arena,
+    /// Kept sorted by id, which is also the order cars were added in.
+    cars: Vec<(CarId, Car)>,
+    next_id: CarId,
+    timestep: FixedTimestep,
+    events: Vec<WorldEvent>,
+    /// Simulated seconds since the world was created.
+    pub time: f64,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new(Arena::default())
+    }
+}
+
+impl World {
+    pub fn new(arena: Arena) -> Self {
+        World {
+            arena,
+            cars: Vec::new(),
+            next_id: 0,
+            timestep: FixedTimestep::default(),
+            events: Vec::new(),
+            time: 0.,
+        }
+    }
+
+    pub fn with_timestep(self, timestep: FixedTimestep) -> Self {
+        World { timestep, ..self }
+    }
+
+    /// The length, in seconds, of a single [`World::step`].
+    pub fn dt(&self) -> f64 {
+        self.timestep.dt
+    }
+
+    pub fn add_car(&mut self, car: Car) -> CarId {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.cars.push((id, car));
+        self.events.push(WorldEvent::CarAdded { car: id });
+        id
+    }
+
+    pub fn remove_car(&mut self, id: CarId) -> Option<Car> {
+        let index = self.index_of(id)?;
+        self.events.push(WorldEvent::CarRemoved { car: id });
+        Some(self.cars.remove(index).1)
+    }
+
+    pub fn car(&self, id: CarId) -> Option<&Car> {
+        self.index_of(id).map(|index| &self.cars[index].1)
+    }
+
+    pub fn car_mut(&mut self, id: CarId) -> Option<&mut Car> {
+        self.index_of(id).map(move |index| &mut self.cars[index].1)
+    }
+
+    pub fn cars(&self) -> impl Iterator<Item = (CarId, &Car)> {
+        self.cars.iter().map(|(id, car)| (*id, car))
+    }
+
+    pub fn len(&self) -> usize {
+        self.cars.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cars.is_empty()
+    }
+
+    /// Replace a car's inputs. Returns `false` if there is no such car.
+    pub fn set_control(&mut self, id: CarId, control: Control) -> bool {
+        self.car_mut(id).map(|car| car.control = control).is_some()
+    }
+
+    pub fn add_obstacle<S: Shape>(&mut self, obstacle: S) {
+        self.arena.obstacles.push(obstacle.collider());
+    }
+
+    /// Advance the simulation by one fixed step of [`World::dt`] seconds.
+    pub fn step(&mut self) {
+        let dt = self.timestep.dt;
+        let solids = self.arena.solids().collect::<Vec<_>>();
+        let walls = self.arena.walls.len();
+        let starts = self
+            .cars
+            .iter()
+            .map(|(_, car)| car.clone())
+            .collect::<Vec<_>>();
+
+        for (id, car) in self.cars.iter_mut() {
+            let surface = self.arena.surface_at(&car.center());
+            car.update_swept_on(dt, &surface, &solids);
+            for (index, impact) in self.arena.impacts(car) {
+                let other = Struck::solid(index, walls);
+                self.events.push(WorldEvent::Collision(CollisionEvent::new(
+                    *id, car, other, impact,
+                )));
+            }
+        }
+
+        // Boxes cover the whole of each car's motion, so fast cars that passed
+        // through each other during the step are still paired up.
+        let mut broad_phase = BroadPhase::new();
+        for (index, (_, car)) in self.cars.iter().enumerate() {
+            broad_phase.insert(index, swept_hitbox(&starts[index], car).aabb());
+        }
+        let mut pairs = broad_phase
+            .pairs()
+            .into_iter()
+            .map(|(a, b)| (a.min(b), a.max(b)))
+            .collect::<Vec<_>>();
+        // Resolve in a fixed order so every simulation of the same inputs agrees.
+        pairs.sort_unstable();
+
+        for (a, b) in pairs {
+            let (head, tail) = self.cars.split_at_mut(b);
+            let ((id_a, car_a), (id_b, car_b)) = (&mut head[a], &mut tail[0]);
+            let (start_a, start_b) = (&starts[a], &starts[b]);
+            if !car_a.overlaps(&*car_b) && could_tunnel(start_a, car_a, start_b, car_b) {
+                // Put both back where they first touched.
+                if let Some(t) = mutual_time_of_impact(start_a, car_a, start_b, car_b) {
+                    *car_a = interpolate(start_a, car_a, t);
+                    *car_b = interpolate(start_b, car_b, t);
+                }
+            }
+            // Sides are judged before the cars are pushed apart.
+            let (before_a, before_b) = (car_a.clone(), car_b.clone());
+            if let Some(impact) = car_a.collide_with(car_b) {
+                let other = Struck::Car {
+                    car: *id_b,
+                    side: before_b.side_of(&impact.point),
+                };
+                self.events.push(WorldEvent::Collision(CollisionEvent::new(
+                    *id_a, &before_a, other, impact,
+                )));
+            }
+        }
+
+        // Sensors look at where everything ended up.
+        let readings = self
+            .cars
+            .iter()
+            .map(|(id, car)| {
+                let lidar = car.lidar.as_ref()?;
+                Some(lidar.scan(car, |ray| {
+                    self.raycast(ray, lidar.range, Some(*id))
+                        .map(|(_, hit)| hit.distance)
+                }))
+            })
+            .collect::<Vec<_>>();
+        for ((_, car), readings) in self.cars.iter_mut().zip(readings) {
+            if let (Some(lidar), Some(readings)) = (car.lidar.as_mut(), readings) {
+                lidar.readings = readings;
+            }
+        }
+
+        self.time += dt;
+    }
+
+    /// Feed `elapsed` seconds of wall-clock time in and run as many fixed steps
+    /// as that adds up to. Returns the number of steps run.
+    pub fn advance(&mut self, elapsed: f64) -> usize {
+        let steps = self.timestep.advance(elapsed);
+        for _ in 0..steps {
+            self.step();
+        }
+        steps
+    }
+
+    /// The nearest car, wall or obstacle `ray` hits within `max_distance`. `ignore`
+    /// leaves a car out, so one can look out from inside itself.
+    pub fn raycast(
+        &self,
+        ray: &Ray,
+        max_distance: f64,
+        ignore: Option<CarId>,
+    ) -> Option<(Struck, RayHit)> {
+        let solid = self
+            .arena
+            .raycast(ray, max_distance)
+            .map(|(index, hit)| (Struck::solid(index, self.arena.walls.len()), hit));
+        let car = self
+            .cars()
+            .filter(|(id, _)| Some(*id) != ignore)
+            .filter_map(|(id, car)| {
+                let hit = car.raycast(ray)?;
+                let side = car.side_of(&hit.point);
+                Some((Struck::Car { car: id, side }, hit))
+            })
+            .filter(|(_, hit)| hit.distance <= max_distance);
+        solid
+            .into_iter()
+            .chain(car)
+            .min_by(|(_, a), (_, b)| a.distance.total_cmp(&b.distance))
+    }
+
+    /// Everything that happened since the last call.
+    pub fn drain_events(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn index_of(&self, id: CarId) -> Option<usize> {
+        self.cars
+            .binary_search_by_key(&id, |(car_id, _)| *car_id)
+            .ok()
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::Circle;
+
+    #[test]
+    fn test_step_drives_and_collides_cars() {
+        let mut world = World::new(Arena::new(1000., 1000.));
+        let rammer = world.add_car(Car::new(500., 700., 60., 80.));
+        let target = world.add_car(Car::new(500., 500., 60., 80.));
+        world.set_control(
+            rammer,
+            Control {
+                forward: true,
+                ..Default::default()
+            },
+        );
+
+        let mut collisions = Vec::new();
+        for _ in 0..120 {
+            world.step();
+            for event in world.drain_events() {
+                if let WorldEvent::Collision(collision) = event {
+                    collisions.push(collision);
+                }
+            }
+        }
+        let first = collisions.first().expect("The cars never collided.");
+        assert_eq!(first.car, rammer);
+        assert_eq!(first.side, Side::Front);
+        assert_eq!(
+            first.other,
+            Struck::Car {
+                car: target,
+                side: Side::Rear
+            }
+        );
+        assert!(first.impulse > 0.);
+        assert!(first.involves(target));
+        assert!(world.car(target).unwrap().y < 500.);
+        assert!((world.time - 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_identical_worlds_stay_identical() {
+        let build = || {
+            let mut world = World::default();
+            world.add_obstacle(Circle::new(Corner { x: 600., y: 400. }, 40.));
+            for i in 0..10 {
+                let id = world
+                    .add_car(Car::new(300. + 50. * i as f64, 400., 30., 40.).with_angle(i as f64));
+                world.set_control(
+                    id,
+                    Control {
+                        forward: true,
+                        left: i % 2 == 0,
+                        ..Default::default()
+                    },
+                );
+            }
+            world
+        };
+        let (mut a, mut b) = (build(), build());
+        a.advance(3.);
+        for _ in 0..a.timestep.max_steps {
+            b.step();
+        }
+        for ((_, car_a), (_, car_b)) in a.cars().zip(b.cars()) {
+            assert_eq!(car_a.x, car_b.x);
+            assert_eq!(car_a.config.angle, car_b.config.angle);
+        }
+    }
+
+    #[test]
+    fn test_fast_cars_do_not_pass_through_each_other() {
+        let mut world = World::new(Arena::new(1000., 1000.));
+        let mut fast = |x: f64, y: f64, angle: f64| {
+            let mut car = Car::new(x, y, 20., 20.).with_angle(angle);
+            car.config.max_speed = 15_000.;
+            car.config.speed = 15_000.;
+            world.add_car(car)
+        };
+        // 250px a step each, head on, with a 180px gap between them.
+        let up = fast(500., 600., 0.);
+        let down = fast(500., 400., std::f64::consts::PI);
+        world.step();
+
+        let collided = world.drain_events().iter().any(|event| {
+            matches!(event, WorldEvent::Collision(collision)
+                if collision.involves(up) && collision.involves(down))
+        });
+        assert!(collided);
+        assert!(world.car(up).unwrap().y > world.car(down).unwrap().y);
+    }
+
+    #[test]
+    fn test_wall_hit_event() {
+        let mut world = World::new(Arena::new(1000., 1000.));
+        let id = world.add_car(Car::new(500., 100., 60., 80.));
+        world.car_mut(id).unwrap().config.speed = 500.;
+
+        let mut hits = Vec::new();
+        for _ in 0..30 {
+            world.step();
+            for event in world.drain_events() {
+                if let WorldEvent::Collision(collision) = event {
+                    hits.push(collision);
+                }
+            }
+        }
+        let hit = hits.iter().find(|hit| hit.impulse > 0.).unwrap();
+        assert!(matches!(hit.other, Struck::Wall { .. }));
+        assert_eq!(hit.side, Side::Front);
+        assert!(hit.normal.y < 0.);
+    }
+
+    #[test]
+    fn test_remove_car() {
+        let mut world = World::default();
+        let first = world.add_car(Car::new(100., 100., 60., 80.));
+        let second = world.add_car(Car::new(300., 100., 60., 80.));
+        assert!(world.remove_car(first).is_some());
+        assert!(world.car(first).is_none());
+        assert!(world.car(second).is_some());
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn test_raycast_finds_nearest_hit() {
+        let mut world = World::new(Arena::new(1000., 1000.));
+        let looking = world.add_car(Car::new(500., 800., 60., 80.));
+        let ahead = world.add_car(Car::new(500., 500., 60., 80.));
+        world.add_obstacle(Circle::new(Corner { x: 500., y: 300. }, 50.));
+        let ray = Ray::from_angle(Corner { x: 500., y: 800. }, 0.);
+
+        let (struck, hit) = world.raycast(&ray, 1000., Some(looking)).unwrap();
+        assert_eq!(
+            struck,
+            Struck::Car {
+                car: ahead,
+                side: Side::Rear
+            }
+        );
+        assert!((hit.distance - 260.).abs() < 1e-9);
+        assert!((hit.normal.y - 1.).abs() < 1e-9);
+
+        world.remove_car(ahead);
+        let (struck, hit) = world.raycast(&ray, 1000., Some(looking)).unwrap();
+        assert_eq!(struck, Struck::Obstacle { index: 0 });
+        assert!((hit.distance - 450.).abs() < 1e-9);
+        assert!(world.raycast(&ray, 400., Some(looking)).is_none());
+
+        let (struck, _) = world
+            .raycast(
+                &Ray::from_angle(Corner { x: 100., y: 100. }, 0.),
+                1000.,
+                None,
+            )
+            .unwrap();
+        assert!(matches!(struck, Struck::Wall { .. }));
+    }
+}
@@ -0,0 +1,252 @@
+//! Just enough of an XML reader for the maps Tiled saves: elements, attributes,
+//! character references, comments and the XML declaration. Text and CDATA are
+//! skipped, since everything a map needs from a `.tmx` file is in attributes.
+
+/// An element, with its attributes in document order and its child elements.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn children_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |child| child.name == name)
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.children.iter().find(|child| child.name == name)
+    }
+}
+
+/// Read a whole document and return its root element. Errors say what went wrong
+/// and at which byte.
+pub(crate) fn parse(text: &str) -> Result<Element, String> {
+    let mut reader = Reader { text, at: 0 };
+    reader.skip_to_tag()?;
+    if reader.rest().is_empty() {
+        return Err("there's no root element".to_string());
+    }
+    let root = reader.element()?;
+    reader.skip_to_tag()?;
+    if !reader.rest().is_empty() {
+        return Err(reader.error("there's more after the root element"));
+    }
+    Ok(root)
+}
+
+struct Reader<'a> {
+    text: &'a str,
+    at: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.at..]
+    }
+
+    fn error(&self, what: &str) -> String {
+        format!("{} at byte {}", what, self.at)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.at += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        match self.rest().find(end) {
+            Some(index) => {
+                self.at += index + end.len();
+                Ok(())
+            }
+            None => Err(self.error(&format!("missing {:?}", end))),
+        }
+    }
+
+    fn expect(&mut self, what: char) -> Result<(), String> {
+        if self.rest().starts_with(what) {
+            self.at += what.len_utf8();
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected {:?}", what)))
+        }
+    }
+
+    /// Skip text, comments, CDATA, processing instructions and doctypes, up to the
+    /// next opening or closing tag or the end of the document.
+    fn skip_to_tag(&mut self) -> Result<(), String> {
+        loop {
+            match self.rest().find('<') {
+                Some(index) => self.at += index,
+                None => {
+                    self.at = self.text.len();
+                    return Ok(());
+                }
+            }
+            let rest = self.rest();
+            if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.skip_past("]]>")?;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!") {
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<&'a str, String> {
+        let rest = self.rest();
+        let length = rest
+            .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '=' | '<'))
+            .unwrap_or(rest.len());
+        if length == 0 {
+            return Err(self.error("expected a name"));
+        }
+        self.at += length;
+        Ok(&rest[..length])
+    }
+
+    /// Read the element whose opening tag starts here.
+    fn element(&mut self) -> Result<Element, String> {
+        self.expect('<')?;
+        let mut element = Element {
+            name: self.name()?.to_string(),
+            ..Default::default()
+        };
+        loop {
+            self.skip_whitespace();
+            if self.rest().starts_with("/>") {
+                self.at += 2;
+                return Ok(element);
+            }
+            if self.rest().starts_with('>') {
+                self.at += 1;
+                break;
+            }
+            let key = self.name()?.to_string();
+            self.skip_whitespace();
+            self.expect('=')?;
+            self.skip_whitespace();
+            let quote = match self.rest().chars().next() {
+                Some(quote @ ('"' | '\'')) => quote,
+                _ => return Err(self.error("expected a quoted attribute value")),
+            };
+            self.at += 1;
+            let length = self
+                .rest()
+                .find(quote)
+                .ok_or_else(|| self.error("unclosed attribute value"))?;
+            let value = unescape(&self.rest()[..length]).map_err(|e| self.error(&e))?;
+            self.at += length + 1;
+            element.attributes.push((key, value));
+        }
+        loop {
+            self.skip_to_tag()?;
+            if self.rest().is_empty() {
+                return Err(self.error(&format!("<{}> is never closed", element.name)));
+            }
+            if self.rest().starts_with("</") {
+                self.at += 2;
+                if self.name()? != element.name {
+                    return Err(self.error(&format!("expected </{}>", element.name)));
+                }
+                self.skip_whitespace();
+                self.expect('>')?;
+                return Ok(element);
+            }
+            element.children.push(self.element()?);
+        }
+    }
+}
+
+/// Replace entity and character references with what they stand for.
+fn unescape(raw: &str) -> Result<String, String> {
+    let mut unescaped = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('&') {
+        unescaped.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let end = rest.find(';').ok_or("unterminated reference")?;
+        let reference = &rest[..end];
+        let code = match reference {
+            "amp" => Some('&' as u32),
+            "lt" => Some('<' as u32),
+            "gt" => Some('>' as u32),
+            "quot" => Some('"' as u32),
+            "apos" => Some('\'' as u32),
+            _ => match reference.strip_prefix("#x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => reference.strip_prefix('#').and_then(|dec| dec.parse().ok()),
+            },
+        };
+        let c = code
+            .and_then(char::from_u32)
+            .ok_or_else(|| format!("unknown reference &{};", reference))?;
+        unescaped.push(c);
+        rest = &rest[end + 1..];
+    }
+    unescaped.push_str(rest);
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_elements_and_attributes() {
+        let root = parse(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <!-- Saved by hand. -->
+            <map name='Fish &amp; chips' note="&lt;&#65;&#x42;&gt;">
+              <data encoding="csv">1,2,3</data>
+              <layer id="1"/>
+              <![CDATA[ <not a="tag"/> ]]>
+              <layer id = "2" ></layer >
+            </map>
+            "#,
+        )
+        .unwrap();
+        assert_eq!(root.name, "map");
+        assert_eq!(root.attribute("name"), Some("Fish & chips"));
+        assert_eq!(root.attribute("note"), Some("<AB>"));
+        assert_eq!(root.attribute("missing"), None);
+        assert_eq!(root.children.len(), 3);
+        let ids = root
+            .children_named("layer")
+            .map(|layer| layer.attribute("id").unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(ids, ["1", "2"]);
+        assert!(root.child("data").unwrap().children.is_empty());
+    }
+
+    #[test]
+    fn test_reject_malformed_documents() {
+        for text in [
+            "",
+            "just text",
+            "<map>",
+            "<map></layer>",
+            "<map/><map/>",
+            r#"<map name="unclosed/>"#,
+            r#"<map name=bare/>"#,
+            r#"<map name="&nbsp;"/>"#,
+            "<!-- unclosed <map/>",
+        ] {
+            assert!(parse(text).is_err(), "{:?} parsed.", text);
+        }
+    }
+}
@@ -1,7 +1,11 @@
-use bumper_core::{Car, CarView};
+use bumper_core::{
+    Behaviour, Bot, Car, CarId, CollisionEvent, Control, Difficulty, Map, Struck, VehicleClasses,
+    World, WorldEvent,
+};
 use serde::{Deserialize, Serialize};
 
 use core::hash::Hash;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::{net::SocketAddr, ops::Deref};
 
@@ -14,12 +18,38 @@ pub trait Id: Hash + Eq + Clone + Send + Sync + std::fmt::Debug + Serialize {}
 
 impl Id for SocketAddr {}
 
-#[derive(Default, Debug, Clone)]
+#[derive(Debug, Clone)]
 pub struct BumperCars<I>
 where
     I: Id,
 {
-    pub players: Arc<Mutex<HashMap<I, Player<I>>>>,
+    /// Which car in `world` each person drives.
+    pub players: Arc<Mutex<HashMap<I, CarId>>>,
+    /// Everybody's cars, simulated here from the controls their clients send.
+    pub world: Arc<Mutex<World>>,
+    /// The level being played. Clients get sent it when they join.
+    pub map: Map,
+    /// What players can choose to drive.
+    pub vehicles: VehicleClasses,
+    /// Computer players, driving cars in `world` alongside everybody else's. Newest last.
+    pub bots: Arc<Mutex<Vec<BotPlayer>>>,
+    /// Bots are added or removed as people come and go to keep this many cars in
+    /// the room. Zero means no bots.
+    pub population: usize,
+    pub difficulty: Difficulty,
+    /// How many players have joined so far, to hand out spawn points in turn.
+    joined: Arc<AtomicUsize>,
+    /// How many bots have been made so far, to name them and pick what they do.
+    bots_made: Arc<AtomicUsize>,
+}
+
+impl<I> Default for BumperCars<I>
+where
+    I: Id,
+{
+    fn default() -> Self {
+        BumperCars::new()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -40,6 +70,16 @@ where
     }
 }
 
+/// A car driven by a [`Bot`] on the server. Clients see it like any other player,
+/// with an id like `"bot-3"`.
+#[derive(Debug, Clone)]
+pub struct BotPlayer {
+    pub id: String,
+    /// The bot's car in [`BumperCars::world`].
+    pub car: CarId,
+    pub driver: Bot,
+}
+
 impl<I> Deref for Player<I>
 where
     I: Id,
@@ -56,9 +96,93 @@ where
     I: Id,
 {
     pub fn new() -> Self {
+        let map = Map::default();
         BumperCars {
             players: Arc::new(Mutex::new(HashMap::new())),
+            world: Arc::new(Mutex::new(World::new(map.arena()))),
+            map,
+            vehicles: VehicleClasses::default(),
+            bots: Arc::new(Mutex::new(Vec::new())),
+            population: 0,
+            difficulty: Difficulty::default(),
+            joined: Arc::new(AtomicUsize::new(0)),
+            bots_made: Arc::new(AtomicUsize::new(0)),
+        }
+    }
+
+    pub fn with_map(self, map: Map) -> Self {
+        BumperCars {
+            world: Arc::new(Mutex::new(World::new(map.arena()))),
+            map,
+            ..self
+        }
+    }
+
+    pub fn with_vehicles(self, vehicles: VehicleClasses) -> Self {
+        BumperCars { vehicles, ..self }
+    }
+
+    /// Fill the room with `difficulty` bots up to `population` cars.
+    pub fn with_bots(self, population: usize, difficulty: Difficulty) -> Self {
+        let game = BumperCars {
+            population,
+            difficulty,
+            ..self
+        };
+        game.balance_bots();
+        game
+    }
+
+    /// Add or remove bots until people and bots together make up the target
+    /// population, or there are no bots left to remove. The newest bots leave first.
+    pub fn balance_bots(&self) {
+        let people = self.players.lock().expect("Couldn't lock players.").len();
+        let mut bots = self.bots.lock().expect("Couldn't lock bots.");
+        let mut world = self.world.lock().expect("Couldn't lock world.");
+        let wanted = self.population.saturating_sub(people);
+        let kept = wanted.min(bots.len());
+        for bot in bots.drain(kept..) {
+            world.remove_car(bot.car);
+        }
+        while bots.len() < wanted {
+            let made = self.bots_made.fetch_add(1, Ordering::Relaxed);
+            let behaviour = match made % 3 {
+                0 => Behaviour::Chaser,
+                1 => Behaviour::WallAvoider,
+                _ => Behaviour::Evader,
+            };
+            let spawn = self.map.spawn(self.joined.fetch_add(1, Ordering::Relaxed));
+            let mut car = self
+                .vehicles
+                .default_class()
+                .car(spawn.position.x, spawn.position.y);
+            car.config.angle = spawn.angle;
+            bots.push(BotPlayer {
+                id: format!("bot-{}", made),
+                car: world.add_car(car),
+                driver: Bot::new(behaviour, self.difficulty, made as u64),
+            });
+        }
+    }
+
+    /// Run the game for one fixed step: bots pick their inputs from where everyone
+    /// is, people drive with the controls their clients last sent, and every car
+    /// moves and collides together. Returns the collisions that happened.
+    pub fn step(&self) -> Vec<CollisionEvent> {
+        let mut bots = self.bots.lock().expect("Couldn't lock bots.");
+        let mut world = self.world.lock().expect("Couldn't lock world.");
+        for bot in bots.iter_mut() {
+            world.drive(bot.car, &mut bot.driver);
         }
+        world.step();
+        world
+            .drain_events()
+            .into_iter()
+            .filter_map(|event| match event {
+                WorldEvent::Collision(collision) => Some(collision),
+                _ => None,
+            })
+            .collect()
     }
 }
 
@@ -74,7 +198,14 @@ where
     fn update_player(&self, id: I, changed_state: Self::PlayerMutation);
     fn send_game_state_to(&self, id: I) -> String;
     fn send_player_state_to(&self, id: I) -> Option<String>;
+    fn send_map_to(&self, id: I) -> String;
+    /// `collisions` for `id` to hear about, with cars named by player id, or `None`
+    /// if there were none.
+    fn send_collisions_to(&self, id: I, collisions: &[CollisionEvent]) -> Option<String>;
     fn create_player(&self, id: I) -> Self::Player;
+    /// Create a player driving the vehicle class called `vehicle`, falling back
+    /// to the default class.
+    fn create_player_as(&self, id: I, vehicle: Option<&str>) -> Self::Player;
 }
 
 impl<I> Game<I> for BumperCars<I>
@@ -82,35 +213,46 @@ where
     I: Id,
 {
     type Player = Player<I>;
-    type PlayerMutation = CarView;
+    type PlayerMutation = Control;
     fn get_player(&self, id: I) -> Self::Player {
         let players = self.players.lock().expect("Couldn't lock players.");
+        let world = self.world.lock().expect("Couldn't lock world.");
 
         players
             .get(&id)
+            .and_then(|car_id| world.car(*car_id))
+            .map(|car| Player::new(id.clone(), car.clone()))
             .unwrap_or_else(|| panic!("Couldn't get player: {:#?} from game state.", id))
-            .clone()
     }
     fn add_player(&self, id: I, player: Self::Player) {
         let mut players = self.players.lock().expect("Couldn't lock players.");
-        players.insert(id, player);
+        let mut world = self.world.lock().expect("Couldn't lock world.");
+        let car_id = world.add_car(player.car);
+        if let Some(replaced) = players.insert(id, car_id) {
+            world.remove_car(replaced);
+        }
     }
     fn remove_player(&self, id: I) -> Option<Self::Player> {
-        let mut players = self.players.lock().expect("Couldn't lock players.");
-        players.remove(&id)
+        let removed = {
+            let mut players = self.players.lock().expect("Couldn't lock players.");
+            let mut world = self.world.lock().expect("Couldn't lock world.");
+            players
+                .remove(&id)
+                .and_then(|car_id| world.remove_car(car_id))
+                .map(|car| Player::new(id, car))
+        };
+        self.balance_bots();
+        removed
     }
 
-    fn update_player(&self, id: I, player: Self::PlayerMutation) {
-        let mut players = self.players.lock().expect("Couldn't lock players.");
-        players.entry(id).and_modify(|mut v| {
-            v.car.x = player.x;
-            v.car.y = player.y;
-            v.car.control.forward = player.forward;
-            v.car.control.left = player.left;
-            v.car.control.right = player.right;
-            v.car.control.reverse = player.reverse;
-            v.car.config = player.config;
-        });
+    fn update_player(&self, id: I, control: Self::PlayerMutation) {
+        let players = self.players.lock().expect("Couldn't lock players.");
+        // Only what the driver is pressing comes from the client. Where the car is,
+        // how fast it goes and how it handles are all the server's to work out.
+        if let Some(car_id) = players.get(&id) {
+            let mut world = self.world.lock().expect("Couldn't lock world.");
+            world.set_control(*car_id, control);
+        }
     }
 
     fn send_game_state_to(&self, id: I) -> String {
@@ -118,10 +260,22 @@ where
             .players
             .lock()
             .expect("Couldn't lock players to send state.");
+        let bots = self.bots.lock().expect("Couldn't lock bots to send state.");
+        let world = self
+            .world
+            .lock()
+            .expect("Couldn't lock world to send state.");
         let data = players
             .iter()
             .filter(|(player_id, _)| player_id != &&id)
-            .map(|(_, player)| player)
+            .filter_map(|(player_id, car_id)| {
+                let car = world.car(*car_id)?;
+                Some(serde_json::json!({ "id": player_id, "car": car }))
+            })
+            .chain(bots.iter().filter_map(|bot| {
+                let car = world.car(bot.car)?;
+                Some(serde_json::json!({ "id": bot.id, "car": car }))
+            }))
             .collect::<Vec<_>>();
         serde_json::to_string(&data).expect("Couldn't serialize players.")
     }
@@ -131,13 +285,63 @@ where
             .players
             .lock()
             .expect("Couldn't lock players to send state.");
-        Some(players.get(&id).unwrap().json())
+        let world = self
+            .world
+            .lock()
+            .expect("Couldn't lock world to send state.");
+        world.car(*players.get(&id)?)?.json().ok()
+    }
+
+    fn send_map_to(&self, _id: I) -> String {
+        serde_json::json!({ "map": self.map }).to_string()
+    }
+
+    fn send_collisions_to(&self, _id: I, collisions: &[CollisionEvent]) -> Option<String> {
+        if collisions.is_empty() {
+            return None;
+        }
+        let players = self.players.lock().expect("Couldn't lock players.");
+        let bots = self.bots.lock().expect("Couldn't lock bots.");
+        // Clients know cars by who drives them, not by their ids in `world`.
+        let mut names = HashMap::new();
+        for (id, car) in players.iter() {
+            names.insert(
+                *car,
+                serde_json::to_value(id).expect("Couldn't serialize player id."),
+            );
+        }
+        for bot in bots.iter() {
+            names.insert(bot.car, serde_json::Value::from(bot.id.clone()));
+        }
+        let name = |car: &CarId| names.get(car).cloned().unwrap_or_default();
+        let collisions = collisions
+            .iter()
+            .map(|collision| {
+                let mut json = serde_json::json!(collision);
+                json["car"] = name(&collision.car);
+                if let Struck::Car { car, .. } = collision.other {
+                    json["other"]["car"] = name(&car);
+                }
+                json
+            })
+            .collect::<Vec<_>>();
+        Some(serde_json::json!({ "collisions": collisions }).to_string())
     }
 
     fn create_player(&self, id: I) -> Self::Player {
-        let car = Car::new(100., 100., 60., 80.);
+        self.create_player_as(id, None)
+    }
+
+    fn create_player_as(&self, id: I, vehicle: Option<&str>) -> Self::Player {
+        let spawn = self.map.spawn(self.joined.fetch_add(1, Ordering::Relaxed));
+        let mut car = self
+            .vehicles
+            .get_or_default(vehicle)
+            .car(spawn.position.x, spawn.position.y);
+        car.config.angle = spawn.angle;
         let player = Player::new(id.clone(), car);
         self.add_player(id, player.clone());
+        self.balance_bots();
         player
     }
 }
@@ -1,5 +1,5 @@
-mod car_view;
 mod game;
 
-pub use car_view::*;
+// Clients and the server share the core's wire type rather than keeping a copy.
+pub use bumper_core::CarView;
 pub use game::*;
@@ -19,12 +19,14 @@
 
 use std::{
     collections::HashMap,
-    env,
-    io::Error as IoError,
+    env, fs,
+    io::{Error as IoError, ErrorKind},
     net::SocketAddr,
     sync::{Arc, Mutex},
+    time::Duration,
 };
 
+use bumper_core::{Difficulty, Map, VehicleClasses, FRAME_DT};
 use bumper_server::{BumperCars, Game};
 
 use log::{debug, error, info, warn};
@@ -36,6 +38,7 @@ use futures::{future, pin_mut, StreamExt, TryStreamExt};
 use futures_channel::mpsc::{unbounded, UnboundedSender};
 
 use tokio::net::{TcpListener, TcpStream};
+use tungstenite::handshake::server::{Request, Response};
 use tungstenite::protocol::Message;
 
 type Tx = UnboundedSender<Message>;
@@ -43,6 +46,9 @@ type PeerMap = Arc<Mutex<HashMap<SocketAddr, Tx>>>;
 // type PeerCarMap = Arc<Mutex<HashMap<SocketAddr, Car>>>;
 // type UuidCarMap = Arc<Mutex<HashMap<Uuid, Car>>>;
 
+/// Clients hear where the cars are every this many steps: 20 times a second.
+const STEPS_PER_BROADCAST: usize = 3;
+
 pub fn set_up_logging() {
     SimpleLogger::new()
         .with_level(log::LevelFilter::Debug)
@@ -60,17 +66,28 @@ async fn handle_connection(
 
     // let example_str = serde_json::to_string(&example).unwrap();
 
-    let ws_stream = tokio_tungstenite::accept_async(raw_stream)
+    let mut vehicle = None;
+    // The error type is tungstenite's, not ours to shrink.
+    #[allow(clippy::result_large_err)]
+    let ws_stream =
+        tokio_tungstenite::accept_hdr_async(raw_stream, |request: &Request, response: Response| {
+            vehicle = requested_vehicle(request.uri().query());
+            Ok(response)
+        })
         .await
         .expect("Error during the websocket handshake occurred");
     debug!("WebSocket connection established: {}", addr);
 
-    debug!("Creating player: {}", addr);
-    game_state.create_player(addr);
+    debug!("Creating player: {} driving {:?}", addr, vehicle);
+    game_state.create_player_as(addr, vehicle.as_deref());
 
     // Insert the write part of this peer to the peer map.
     let (tx, rx) = unbounded();
 
+    debug!("Sending map to: {}", addr);
+    tx.unbounded_send(Message::Text(game_state.send_map_to(addr)))
+        .expect("Couldn't send map.");
+
     debug!("Sending player state to: {}", addr);
     tx.unbounded_send(Message::Text(
         game_state
@@ -91,71 +108,120 @@ async fn handle_connection(
 
     let (outgoing, incoming) = ws_stream.split();
 
-    let broadcast_incoming = incoming.try_for_each(|msg| {
+    // Clients only tell us what their drivers are pressing, whenever it changes.
+    // Everyone hears where the cars went from `run_game`.
+    let receive_controls = incoming.try_for_each(|msg| {
         debug!("Received a message from {}", addr);
 
-        if let Ok(self_car_view) = serde_json::from_str(msg.to_text().unwrap()) {
-            game_state.update_player(addr, self_car_view);
+        if let Ok(control) = serde_json::from_str(msg.to_text().unwrap()) {
+            game_state.update_player(addr, control);
         } else {
             warn!("Couldn't parse message: {}", msg.to_text().unwrap());
         }
 
-        let peers = peer_map.lock().unwrap();
-
-        // We want to broadcast the message to everyone except ourselves.
-        let broadcast_recipients = peers.iter().filter(|(peer_addr, _)| peer_addr != &&addr);
-
-        for (recp_addr, recp_socket) in broadcast_recipients {
-            let to_send = game_state.send_game_state_to(*recp_addr);
-            debug!("Sending {} to {}", to_send, recp_addr);
-            if let Err(e) = recp_socket.unbounded_send(Message::Text(to_send)) {
-                game_state.remove_player(*recp_addr);
-                error!("Failed to send to {}: {}", recp_addr, e);
-            }
-        }
-
         future::ok(())
     });
 
     let receive_from_others = rx.map(Ok).forward(outgoing);
 
-    {
-        let peers = peer_map.lock().unwrap();
-
-        // We want to broadcast the message to everyone except ourselves.
-        let broadcast_recipients = peers.iter().filter(|(peer_addr, _)| peer_addr != &&addr);
-
-        for (recp_addr, recp_socket) in broadcast_recipients {
-            let to_send = game_state.send_game_state_to(*recp_addr);
-            debug!("Sending {} to {}", to_send, recp_addr);
-            if let Err(e) = recp_socket.unbounded_send(Message::Text(to_send)) {
-                game_state.remove_player(*recp_addr);
-                error!("Failed to send to {}: {}", recp_addr, e);
-            }
-        }
-    }
-
-    pin_mut!(broadcast_incoming, receive_from_others);
-    future::select(broadcast_incoming, receive_from_others).await;
+    pin_mut!(receive_controls, receive_from_others);
+    future::select(receive_controls, receive_from_others).await;
 
     debug!("{} disconnected", &addr);
+    peer_map.lock().unwrap().remove(&addr);
     debug!("Removing player: {}", addr);
     game_state.remove_player(addr);
+}
 
-    {
-        let peers = peer_map.lock().unwrap();
+/// The vehicle class a client asked for with `?vehicle=<name>` on the socket URL.
+fn requested_vehicle(query: Option<&str>) -> Option<String> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("vehicle="))
+        .map(str::to_string)
+}
 
-        // We want to broadcast the message to everyone except ourselves.
-        let broadcast_recipients = peers.iter().filter(|(peer_addr, _)| peer_addr != &&addr);
+/// Load vehicle classes from the JSON file named by `BUMPER_VEHICLES`, or use the
+/// built-in ones.
+fn load_vehicles() -> Result<VehicleClasses, IoError> {
+    match env::var("BUMPER_VEHICLES") {
+        Ok(path) => VehicleClasses::from_json(&fs::read_to_string(&path)?)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, format!("{}: {}", path, e))),
+        Err(_) => Ok(VehicleClasses::default()),
+    }
+}
 
-        for (recp_addr, recp_socket) in broadcast_recipients {
-            let to_send = game_state.send_game_state_to(*recp_addr);
-            debug!("Sending {} to {}", to_send, recp_addr);
-            if let Err(e) = recp_socket.unbounded_send(Message::Text(to_send)) {
-                game_state.remove_player(*recp_addr);
-                error!("Failed to send to {}: {}", recp_addr, e);
+/// Load the map from the file named by `BUMPER_MAP`, or use the built-in one.
+/// Files ending in `.tmj` or `.tmx` are read as maps saved by Tiled.
+fn load_map() -> Result<Map, IoError> {
+    let path = match env::var("BUMPER_MAP") {
+        Ok(path) => path,
+        Err(_) => return Ok(Map::default()),
+    };
+    let text = fs::read_to_string(&path)?;
+    let map = if path.ends_with(".tmj") {
+        Map::from_tiled_json(&text).map_err(|e| e.to_string())
+    } else if path.ends_with(".tmx") {
+        Map::from_tiled_xml(&text).map_err(|e| e.to_string())
+    } else {
+        Map::from_json(&text).map_err(|e| e.to_string())
+    };
+    map.map_err(|e| IoError::new(ErrorKind::InvalidData, format!("{}: {}", path, e)))
+}
+
+/// How many cars bots should keep the room at, from `BUMPER_BOTS`, and how good
+/// they are, from `BUMPER_BOT_DIFFICULTY` (`easy`, `medium` or `hard`).
+fn load_bots() -> Result<(usize, Difficulty), IoError> {
+    let invalid = |name: &str, value: String| {
+        IoError::new(
+            ErrorKind::InvalidInput,
+            format!("{} can't be {:?}.", name, value),
+        )
+    };
+    let population = match env::var("BUMPER_BOTS") {
+        Ok(value) => value.parse().map_err(|_| invalid("BUMPER_BOTS", value))?,
+        Err(_) => 0,
+    };
+    let difficulty = match env::var("BUMPER_BOT_DIFFICULTY") {
+        Ok(value) => serde_json::from_value(serde_json::Value::String(value.clone()))
+            .map_err(|_| invalid("BUMPER_BOT_DIFFICULTY", value))?,
+        Err(_) => Difficulty::default(),
+    };
+    Ok((population, difficulty))
+}
+
+/// Step the game at the simulation's frame rate and, every few steps, show everyone
+/// where the cars went (their own car, then everybody else's) and what they hit
+/// since last time.
+async fn run_game(peer_map: PeerMap, game_state: BumperCars<SocketAddr>) {
+    let mut interval = tokio::time::interval(Duration::from_secs_f64(FRAME_DT));
+    let mut collisions = Vec::new();
+    for step in 1usize.. {
+        interval.tick().await;
+        collisions.extend(game_state.step());
+        if step % STEPS_PER_BROADCAST != 0 {
+            continue;
+        }
+        // Serialising takes a while, so don't keep people from joining or leaving meanwhile.
+        let peers = peer_map
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(addr, tx)| (*addr, tx.clone()))
+            .collect::<Vec<_>>();
+        for (recp_addr, recp_socket) in peers {
+            let messages = game_state
+                .send_player_state_to(recp_addr)
+                .into_iter()
+                .chain([game_state.send_game_state_to(recp_addr)])
+                .chain(game_state.send_collisions_to(recp_addr, &collisions));
+            for to_send in messages {
+                if let Err(e) = recp_socket.unbounded_send(Message::Text(to_send)) {
+                    error!("Failed to send to {}: {}", recp_addr, e);
+                }
             }
         }
+        collisions.clear();
     }
 }
 
@@ -167,7 +233,34 @@ async fn main() -> Result<(), IoError> {
         .unwrap_or_else(|| "127.0.0.1:8080".to_string());
     let peer_map = PeerMap::new(Mutex::new(HashMap::new()));
     // let peer_car_map = PeerCarMap::new(Mutex::new(HashMap::new()));
-    let game_state = BumperCars::new();
+    let vehicles = load_vehicles()?;
+    info!(
+        "Vehicle classes: {:?}",
+        vehicles
+            .classes()
+            .iter()
+            .map(|class| &class.name)
+            .collect::<Vec<_>>()
+    );
+    let map = load_map()?;
+    info!("Map: {:?}", map.name);
+    // Every class has to fit on every spawn point, not just the standard one.
+    for class in vehicles.classes() {
+        map.validate_spawns(class.width, class.height)
+            .map_err(|e| {
+                IoError::new(
+                    ErrorKind::InvalidData,
+                    format!("Map {:?} for the {} class: {}", map.name, class.name, e),
+                )
+            })?;
+    }
+    let (population, difficulty) = load_bots()?;
+    info!("Bots: up to {} cars, {:?}", population, difficulty);
+    let game_state = BumperCars::new()
+        .with_vehicles(vehicles)
+        .with_map(map)
+        .with_bots(population, difficulty);
+    tokio::spawn(run_game(peer_map.clone(), game_state.clone()));
 
     // Create the event loop and TCP listener we'll accept connections on.
     let try_socket = TcpListener::bind(&addr).await;
@@ -13,7 +13,7 @@ use wasm_bindgen::prelude::*;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CarConfig(bumper_core::CarConfig);
 
-#[wasm_bindgen(inspectable, js_name = "CarConfig")]
+#[wasm_bindgen(js_class = "CarConfig")]
 impl CarConfig {
     #[wasm_bindgen(constructor)]
     pub fn new(
@@ -23,15 +23,42 @@ impl CarConfig {
         friction: f64,
         angle: f64,
         angle_delta: f64,
-    ) -> Self {
-        CarConfig(bumper_core::CarConfig {
+        mass: f64,
+        restitution: f64,
+        lateral_speed: f64,
+        grip: f64,
+        angular_speed: f64,
+        angular_damping: f64,
+        handbrake_grip: Option<f64>,
+        handbrake_steering: Option<f64>,
+        boost_capacity: Option<f64>,
+        boost_recharge: Option<f64>,
+        boost_factor: Option<f64>,
+    ) -> Result<CarConfig, JsValue> {
+        let defaults = bumper_core::CarConfig::default();
+        let config = bumper_core::CarConfig {
             speed,
+            lateral_speed,
             acceleration,
             max_speed,
             friction,
             angle,
             angle_delta,
-        })
+            angular_speed,
+            angular_damping,
+            mass,
+            restitution,
+            grip,
+            handbrake_grip: handbrake_grip.unwrap_or(defaults.handbrake_grip),
+            handbrake_steering: handbrake_steering.unwrap_or(defaults.handbrake_steering),
+            boost_capacity: boost_capacity.unwrap_or(defaults.boost_capacity),
+            boost_recharge: boost_recharge.unwrap_or(defaults.boost_recharge),
+            boost_factor: boost_factor.unwrap_or(defaults.boost_factor),
+        };
+        config
+            .validate()
+            .map(|_| CarConfig(config))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
     }
 
     #[wasm_bindgen(getter)]
@@ -64,13 +91,57 @@ impl CarConfig {
     pub fn angle_delta(&self) -> f64 {
         self.0.angle_delta
     }
+    #[wasm_bindgen(getter)]
+    pub fn mass(&self) -> f64 {
+        self.0.mass
+    }
+    #[wasm_bindgen(getter)]
+    pub fn restitution(&self) -> f64 {
+        self.0.restitution
+    }
+    #[wasm_bindgen(getter)]
+    pub fn lateral_speed(&self) -> f64 {
+        self.0.lateral_speed
+    }
+    #[wasm_bindgen(getter)]
+    pub fn grip(&self) -> f64 {
+        self.0.grip
+    }
+    #[wasm_bindgen(getter)]
+    pub fn angular_speed(&self) -> f64 {
+        self.0.angular_speed
+    }
+    #[wasm_bindgen(getter)]
+    pub fn angular_damping(&self) -> f64 {
+        self.0.angular_damping
+    }
+    #[wasm_bindgen(getter)]
+    pub fn handbrake_grip(&self) -> f64 {
+        self.0.handbrake_grip
+    }
+    #[wasm_bindgen(getter)]
+    pub fn handbrake_steering(&self) -> f64 {
+        self.0.handbrake_steering
+    }
+    #[wasm_bindgen(getter)]
+    pub fn boost_capacity(&self) -> f64 {
+        self.0.boost_capacity
+    }
+    #[wasm_bindgen(getter)]
+    pub fn boost_recharge(&self) -> f64 {
+        self.0.boost_recharge
+    }
+    #[wasm_bindgen(getter)]
+    pub fn boost_factor(&self) -> f64 {
+        self.0.boost_factor
+    }
 }
 
 #[wasm_bindgen(inspectable)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CarView(bumper_core::CarView);
 
-#[wasm_bindgen(js_name = "CarView")]
+#[wasm_bindgen(js_class = "CarView")]
 impl CarView {
     #[wasm_bindgen(constructor)]
     pub fn new(
@@ -83,6 +154,10 @@ impl CarView {
         right: bool,
         forward: bool,
         reverse: bool,
+        throttle: Option<f64>,
+        steering: Option<f64>,
+        handbrake: Option<bool>,
+        boost: Option<bool>,
     ) -> Self {
         CarView(bumper_core::CarView {
             x,
@@ -94,6 +169,10 @@ impl CarView {
             right,
             forward,
             reverse,
+            throttle: throttle.unwrap_or_default(),
+            steering: steering.unwrap_or_default(),
+            handbrake: handbrake.unwrap_or_default(),
+            boost: boost.unwrap_or_default(),
         })
     }
     #[wasm_bindgen(getter)]
@@ -125,7 +204,7 @@ pub struct Car(bumper_core::Car);
 #[wasm_bindgen(inspectable)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CarPosition(bumper_core::CarPosition);
-#[wasm_bindgen(js_name = "CarPosition")]
+#[wasm_bindgen(js_class = "CarPosition")]
 impl CarPosition {
     #[wasm_bindgen(constructor)]
     pub fn new(x: f64, y: f64, width: f64, height: f64, angle: f64) -> Self {
@@ -139,7 +218,7 @@ impl CarPosition {
     }
 }
 
-#[wasm_bindgen(js_name = "Car")]
+#[wasm_bindgen(js_class = "Car")]
 impl Car {
     #[wasm_bindgen(constructor)]
     pub fn new(x: f64, y: f64, width: f64, height: f64) -> Car {
@@ -151,22 +230,55 @@ impl Car {
             height,
             config: bumper_core::CarConfig::default(),
             control: bumper_core::Control::default(),
+            boost: bumper_core::CarConfig::default().boost_capacity,
+            lidar: None,
         })
     }
 
+    /// Build a car from the JSON the server sends, keeping the vehicle class and
+    /// facing it was given.
+    #[wasm_bindgen(js_name = "fromJson")]
+    pub fn from_json(json: &str) -> Result<Car, JsValue> {
+        bumper_core::Car::from_json(json)
+            .map(Car)
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
     #[wasm_bindgen(getter)]
     pub fn config(&self) -> CarConfig {
         CarConfig(self.0.config)
     }
 
-    pub fn update(&mut self) {
-        self.0.update()
+    /// Advance the car by `dt` seconds, or by a single 60 Hz frame when `dt` is omitted.
+    pub fn update(&mut self, dt: Option<f64>) {
+        match dt {
+            Some(dt) => self.0.update(dt),
+            None => self.0.update_frame(),
+        }
     }
 
     pub fn collides(&self, car: &Car) -> bool {
         self.0.collides(&car.0)
     }
 
+    #[wasm_bindgen(js_name = "resolveCollision")]
+    pub fn resolve_collision(&mut self, car: &mut Car) -> bool {
+        self.0.resolve_collision(&mut car.0)
+    }
+
+    /// Advance the car by `dt` seconds inside `arena`, stopping it at the first wall
+    /// or obstacle in its way and bouncing it off.
+    #[wasm_bindgen(js_name = "updateInArena")]
+    pub fn update_in_arena(&mut self, dt: f64, arena: &Arena) -> bool {
+        arena.0.update_car(&mut self.0, dt)
+    }
+
+    /// Keep the car inside `arena`, bouncing it off any wall it hit.
+    #[wasm_bindgen(js_name = "collideArena")]
+    pub fn collide_arena(&mut self, arena: &Arena) -> bool {
+        arena.0.collide(&mut self.0)
+    }
+
     #[wasm_bindgen(js_name = "collidesPosition")]
     pub fn collides_position(&self, car_position: &CarPosition) -> bool {
         self.0.collides_position(&car_position.0)
@@ -241,4 +353,262 @@ impl Car {
     pub fn set_reverse(&mut self, reverse: bool) {
         self.0.control.reverse = reverse;
     }
+
+    /// Analog throttle in `[-1, 1]`, on top of `forward` and `reverse`.
+    #[wasm_bindgen(getter)]
+    pub fn throttle(&self) -> f64 {
+        self.0.control.throttle
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_throttle(&mut self, throttle: f64) {
+        self.0.control.throttle = throttle;
+    }
+
+    /// Analog steering in `[-1, 1]`, positive to the right, on top of `left` and `right`.
+    #[wasm_bindgen(getter)]
+    pub fn steering(&self) -> f64 {
+        self.0.control.steering
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_steering(&mut self, steering: f64) {
+        self.0.control.steering = steering;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn handbrake(&self) -> bool {
+        self.0.control.handbrake
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_handbrake(&mut self, handbrake: bool) {
+        self.0.control.handbrake = handbrake;
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn boost(&self) -> bool {
+        self.0.control.boost
+    }
+
+    #[wasm_bindgen(setter)]
+    pub fn set_boost(&mut self, boost: bool) {
+        self.0.control.boost = boost;
+    }
+
+    /// Seconds of boost left in the meter.
+    #[wasm_bindgen(getter, js_name = "boostMeter")]
+    pub fn boost_meter(&self) -> f64 {
+        self.0.boost
+    }
+
+    /// Mount `rays` distance sensors spread over `field_of_view` radians that see up
+    /// to `range` px. Throws if there are no rays, the range isn't positive or the
+    /// field of view is more than a full turn.
+    #[wasm_bindgen(js_name = "setLidar")]
+    pub fn set_lidar(
+        &mut self,
+        rays: usize,
+        field_of_view: f64,
+        range: f64,
+    ) -> Result<(), JsValue> {
+        let lidar = bumper_core::Lidar::new(rays, field_of_view, range)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        self.0.lidar = Some(lidar);
+        Ok(())
+    }
+
+    /// The latest sensor readings from left to right, each the distance to the nearest
+    /// hit as a fraction of the range. Empty without sensors.
+    #[wasm_bindgen(getter, js_name = "lidarReadings")]
+    pub fn lidar_readings(&self) -> Vec<f64> {
+        self.0
+            .lidar
+            .as_ref()
+            .map(|lidar| lidar.readings.clone())
+            .unwrap_or_default()
+    }
+}
+
+#[wasm_bindgen(inspectable)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Arena(bumper_core::Arena);
+
+#[wasm_bindgen(js_class = "Arena")]
+impl Arena {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: f64, height: f64) -> Self {
+        Arena(bumper_core::Arena::new(width, height))
+    }
+
+    /// Build the arena a map describes from the map JSON the server sends on join.
+    #[wasm_bindgen(js_name = "fromMapJson")]
+    pub fn from_map_json(json: &str) -> Result<Arena, JsValue> {
+        bumper_core::Map::from_json(json)
+            .map(|map| Arena(map.arena()))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> f64 {
+        self.0.width
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> f64 {
+        self.0.height
+    }
+
+    /// Refresh `car`'s sensor readings against the walls and obstacles.
+    pub fn scan(&self, car: &mut Car) {
+        self.0.scan(&mut car.0)
+    }
+
+    /// The outline of every wall and obstacle, one `[x0, y0, x1, y1, ...]` array per shape.
+    pub fn solids(&self) -> js_sys::Array {
+        self.0
+            .solids()
+            .map(|solid| {
+                let flat = solid
+                    .outline(32)
+                    .iter()
+                    .flat_map(|corner| [corner.x, corner.y])
+                    .collect::<Vec<f64>>();
+                js_sys::Float64Array::from(&flat[..])
+            })
+            .collect()
+    }
+
+    /// Every surface zone, bottom first, as `{ kind, outline }` objects where `kind`
+    /// is `"ice"`, `"mud"` or `"boost_pad"` and `outline` is laid out like [`Arena::solids`].
+    pub fn surfaces(&self) -> js_sys::Array {
+        self.0
+            .surfaces
+            .iter()
+            .map(|zone| {
+                let flat = zone
+                    .area
+                    .outline(32)
+                    .iter()
+                    .flat_map(|corner| [corner.x, corner.y])
+                    .collect::<Vec<f64>>();
+                let kind = serde_json::to_value(zone.kind)
+                    .ok()
+                    .and_then(|kind| kind.as_str().map(str::to_string))
+                    .unwrap_or_default();
+                let object = js_sys::Object::new();
+                let _ = js_sys::Reflect::set(&object, &"kind".into(), &kind.into());
+                let _ = js_sys::Reflect::set(
+                    &object,
+                    &"outline".into(),
+                    &js_sys::Float64Array::from(&flat[..]),
+                );
+                object
+            })
+            .collect()
+    }
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Clone)]
+pub struct World(bumper_core::World);
+
+#[wasm_bindgen(js_class = "World")]
+impl World {
+    #[wasm_bindgen(constructor)]
+    pub fn new(arena: &Arena) -> Self {
+        World(bumper_core::World::new(arena.0.clone()))
+    }
+
+    /// Add a copy of `car` to the world and return its id.
+    #[wasm_bindgen(js_name = "addCar")]
+    pub fn add_car(&mut self, car: &Car) -> u32 {
+        self.0.add_car(car.0.clone())
+    }
+
+    #[wasm_bindgen(js_name = "removeCar")]
+    pub fn remove_car(&mut self, id: u32) -> bool {
+        self.0.remove_car(id).is_some()
+    }
+
+    /// Copy the inputs of `car` onto the world's car `id`.
+    #[wasm_bindgen(js_name = "setControl")]
+    pub fn set_control(&mut self, id: u32, car: &Car) -> bool {
+        self.0.set_control(id, car.0.control)
+    }
+
+    /// Copy the state of the world's car `id` back into `car`, keeping its inputs.
+    #[wasm_bindgen(js_name = "readCar")]
+    pub fn read_car(&self, id: u32, car: &mut Car) -> bool {
+        match self.0.car(id) {
+            Some(simulated) => {
+                car.0 = bumper_core::Car {
+                    control: car.0.control,
+                    ..simulated.clone()
+                };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the world's car `id` with the server's copy of it, given as JSON,
+    /// keeping the inputs it already has.
+    #[wasm_bindgen(js_name = "syncCar")]
+    pub fn sync_car(&mut self, id: u32, json: &str) -> Result<bool, JsValue> {
+        let synced =
+            bumper_core::Car::from_json(json).map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(match self.0.car_mut(id) {
+            Some(car) => {
+                *car = bumper_core::Car {
+                    control: car.control,
+                    ..synced
+                };
+                true
+            }
+            None => false,
+        })
+    }
+
+    pub fn step(&mut self) {
+        self.0.step()
+    }
+
+    /// Add `elapsed` seconds and run as many fixed steps as that adds up to.
+    pub fn advance(&mut self, elapsed: f64) -> usize {
+        self.0.advance(elapsed)
+    }
+
+    /// Everything that happened since the last call, as a JSON array.
+    #[wasm_bindgen(js_name = "drainEvents")]
+    pub fn drain_events(&mut self) -> String {
+        serde_json::to_string(&self.0.drain_events()).unwrap_or_else(|_| "[]".to_string())
+    }
+}
+
+#[wasm_bindgen(inspectable)]
+#[derive(Debug, Clone)]
+pub struct FixedTimestep(bumper_core::FixedTimestep);
+
+#[wasm_bindgen(js_class = "FixedTimestep")]
+impl FixedTimestep {
+    #[wasm_bindgen(constructor)]
+    pub fn new(dt: Option<f64>) -> Self {
+        FixedTimestep(dt.map_or_else(Default::default, bumper_core::FixedTimestep::new))
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn dt(&self) -> f64 {
+        self.0.dt
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn alpha(&self) -> f64 {
+        self.0.alpha()
+    }
+
+    /// Add `elapsed` seconds and return how many steps of `dt` to run now.
+    pub fn advance(&mut self, elapsed: f64) -> usize {
+        self.0.advance(elapsed)
+    }
 }
@@ -1,5 +1,5 @@
 import init from "./web/bumper_web.js";
-import { Car, CarPosition } from "./web/bumper_web.js";
+import { Arena, Car, World } from "./web/bumper_web.js";
 
 let canvas = document.getElementById("canvas");
 let ctx = canvas.getContext("2d");
@@ -7,14 +7,51 @@ let ctx = canvas.getContext("2d");
 let cars = new Map();
 
 let car;
-let currentPos;
-let prevPos;
-
-function dispatchCarMove(c) {
-  const event = new CustomEvent("carMoved", { detail: c });
-  // console.log("Dispatch fired:", c);
-  // console.log("car moved! prevPos:", prevPos, "currentPos:", currentPos);
-  canvas.dispatchEvent(event);
+let carId;
+let arena;
+let world;
+
+// What the driver is pressing, in the shape of the server's `Control`.
+let control = {
+  forward: false,
+  reverse: false,
+  left: false,
+  right: false,
+  throttle: 0,
+  steering: 0,
+  handbrake: false,
+  boost: false,
+};
+
+// Which part of the control each key holds down.
+const KEYS = {
+  ArrowLeft: "left",
+  ArrowRight: "right",
+  ArrowUp: "forward",
+  ArrowDown: "reverse",
+  " ": "handbrake",
+  Shift: "boost",
+};
+
+let lastFrameAt;
+
+// Where cars recently hit something, and until when to show it.
+let sparks = [];
+const SPARK_MS = 300;
+
+/**
+ * Set one part of the control on our car, and tell the server if it changed.
+ * @param {Car} car
+ * @param {string} key
+ * @param {boolean | number} value
+ */
+function setControl(car, key, value) {
+  car[key] = value;
+  if (control[key] === value) {
+    return;
+  }
+  control = { ...control, [key]: value };
+  canvas.dispatchEvent(new CustomEvent("controlChanged", { detail: control }));
 }
 
 /**
@@ -23,40 +60,37 @@ function dispatchCarMove(c) {
  */
 function registerKeyPresses(car) {
   window.onkeydown = (event) => {
-    switch (event.key) {
-      case "ArrowLeft":
-        car.left = true;
-        break;
-      case "ArrowRight":
-        car.right = true;
-        break;
-      case "ArrowUp":
-        car.forward = true;
-        break;
-      case "ArrowDown":
-        car.reverse = true;
-        break;
+    if (KEYS[event.key]) {
+      setControl(car, KEYS[event.key], true);
     }
   };
 
   window.onkeyup = (event) => {
-    switch (event.key) {
-      case "ArrowLeft":
-        car.left = false;
-        break;
-      case "ArrowRight":
-        car.right = false;
-        break;
-      case "ArrowUp":
-        car.forward = false;
-        break;
-      case "ArrowDown":
-        car.reverse = false;
-        break;
+    if (KEYS[event.key]) {
+      setControl(car, KEYS[event.key], false);
     }
   };
 }
 
+/**
+ * Copy the first connected gamepad's left stick and triggers onto the car's
+ * analog controls.
+ * @param {Car} car
+ */
+function readGamepad(car) {
+  const pad = navigator.getGamepads
+    ? navigator.getGamepads().find((pad) => pad)
+    : undefined;
+  if (!pad) {
+    return;
+  }
+  const deadzone = (value) => (Math.abs(value) < 0.1 ? 0 : value);
+  setControl(car, "steering", deadzone(pad.axes[0] || 0));
+  const gas = pad.buttons[7] ? pad.buttons[7].value : 0;
+  const brake = pad.buttons[6] ? pad.buttons[6].value : 0;
+  setControl(car, "throttle", gas - brake);
+}
+
 async function onInit() {
   await setup();
   // canvas.dispatchEvent();
@@ -65,26 +99,42 @@ async function onInit() {
   // let view = car.as_view();
   // console.log(view.toString());
   setTimeout(() => {
-    animate();
+    requestAnimationFrame(animate);
   }, 5000);
 }
 
 /**
  *
  */
-function animate() {
+function animate(now) {
   ctx.clearRect(0, 0, canvas.width, canvas.height);
 
-  if (car) {
+  // The floor goes underneath the cars.
+  if (arena) {
+    drawArena(arena, ctx);
+  }
+
+  const elapsed = lastFrameAt === undefined ? 0 : (now - lastFrameAt) / 1000;
+  lastFrameAt = now;
+
+  if (car && world) {
     draw(car, ctx);
-    car.update();
-    currentPos.x = car.x;
-    currentPos.y = car.y;
+    if (carId === undefined) {
+      carId = world.addCar(car);
+    }
+    readGamepad(car);
+    world.setControl(carId, car);
+    world.advance(elapsed);
+    world.readCar(carId, car);
+    // Only the server's collisions count; see the "collisions" listener.
+    world.drainEvents();
   }
 
   if (cars) {
     drawAllCars(cars, ctx);
   }
+  sparks = sparks.filter((spark) => spark.until > now);
+  drawSparks(sparks, ctx);
   requestAnimationFrame(animate);
 }
 
@@ -94,50 +144,32 @@ async function setup() {
   canvas.width = window.innerWidth;
   canvas.height = window.innerHeight;
 
+  canvas.addEventListener("map", (e) => {
+    arena = Arena.fromMapJson(JSON.stringify(e.detail));
+    world = new World(arena);
+    carId = undefined;
+  });
+
+  // The server runs the real game; snap our car to wherever it says it is.
+  canvas.addEventListener("player", (e) => {
+    if (world && carId !== undefined) {
+      world.syncCar(carId, JSON.stringify(e.detail));
+    }
+  });
+
+  canvas.addEventListener("collisions", (e) => {
+    const until = performance.now() + SPARK_MS;
+    e.detail
+      .filter((collision) => collision.impulse > 0)
+      .forEach(({ point }) => sparks.push({ x: point.x, y: point.y, until }));
+  });
+
   canvas.addEventListener("cars", (e) => {
     const { initial, data } = e.detail;
-    // debugger;
-    console.log("initial", initial);
     if (initial) {
-      car = new Car(data.x, data.y, data.width, data.height);
+      // The server picked the vehicle class and spawn; take its car as it is.
+      car = Car.fromJson(JSON.stringify(data));
       registerKeyPresses(car);
-      prevPos = { x: car.x, y: car.y };
-      currentPos = new Proxy(
-        {
-          x: car.x,
-          y: car.y,
-        },
-        {
-          set: function (target, key, value) {
-            if (prevPos.x !== target.x || prevPos.y !== target.y) {
-              dispatchCarMove(car);
-              cars.forEach((v, ...rest) => {
-                console.log("v: ", v);
-
-                let position = new CarPosition(
-                  v.x,
-                  v.y,
-                  v.width,
-                  v.height,
-                  v.config.angle
-                );
-                if (car.collidesPosition(position)) {
-                  console.log("Car", car, "collides", v);
-                }
-              });
-              prevPos = {
-                x: target.x,
-                y: target.y,
-              };
-            }
-            target[key] = value;
-            return true;
-          },
-          get: function (target, key) {
-            return target[key];
-          },
-        }
-      );
 
       // console.log("Setting car:", car);
     } else {
@@ -180,6 +212,55 @@ function drawAllCars(allCars, ctx) {
   // });
 }
 
+/**
+ *
+ * @param {{ x: number, y: number }[]} sparks
+ * @param {CanvasRenderingContext2D} ctx
+ */
+function drawSparks(sparks, ctx) {
+  ctx.fillStyle = "#ffb300";
+  sparks.forEach(({ x, y }) => {
+    ctx.beginPath();
+    ctx.arc(x, y, 6, 0, 2 * Math.PI);
+    ctx.fill();
+  });
+}
+
+const SURFACE_COLORS = {
+  ice: "#cdeefd",
+  mud: "#7a5a3a",
+  boost_pad: "#f6c945",
+};
+
+/**
+ *
+ * @param {Arena} arena
+ * @param {CanvasRenderingContext2D} ctx
+ */
+function drawArena(arena, ctx) {
+  arena.surfaces().forEach(({ kind, outline }) => {
+    ctx.fillStyle = SURFACE_COLORS[kind] || "#dddddd";
+    ctx.beginPath();
+    ctx.moveTo(outline[0], outline[1]);
+    for (let i = 2; i < outline.length; i += 2) {
+      ctx.lineTo(outline[i], outline[i + 1]);
+    }
+    ctx.closePath();
+    ctx.fill();
+  });
+
+  ctx.fillStyle = "#2b2b2b";
+  arena.solids().forEach((vertices) => {
+    ctx.beginPath();
+    ctx.moveTo(vertices[0], vertices[1]);
+    for (let i = 2; i < vertices.length; i += 2) {
+      ctx.lineTo(vertices[i], vertices[i + 1]);
+    }
+    ctx.closePath();
+    ctx.fill();
+  });
+}
+
 /**
  *
  * @param {Car} car
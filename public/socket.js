@@ -1,5 +1,6 @@
 // let worker;
 let ws;
+let joined = false;
 let canvas = document.getElementById("canvas");
 
 function createEventDispatcher(elem) {
@@ -13,10 +14,10 @@ function createEventDispatcher(elem) {
 
 function attachListener() {
   if (canvas) {
-    console.log("Attaching carMoved to canvas.");
-    canvas.addEventListener("carMoved", (e) => {
-      const newCar = e.detail;
-      ws.send(JSON.stringify(newCar));
+    console.log("Attaching controlChanged to canvas.");
+    // The server drives our car; all it needs from us is what we're pressing.
+    canvas.addEventListener("controlChanged", (e) => {
+      ws.send(JSON.stringify(e.detail));
     });
   } else {
     console.log("No canvas found.");
@@ -40,7 +41,14 @@ function onMessage(message) {
   let raw = message.data;
   let data = JSON.parse(raw);
 
-  if (!Array.isArray(data)) {
+  if (data.map) {
+    const dispatch = createEventDispatcher(document.getElementById("canvas"));
+    dispatch("map", data.map);
+  } else if (data.collisions) {
+    const dispatch = createEventDispatcher(document.getElementById("canvas"));
+    dispatch("collisions", data.collisions);
+  } else if (!Array.isArray(data) && !joined) {
+    joined = true;
     setTimeout(() => {
       const dispatch = createEventDispatcher(document.getElementById("canvas"));
       dispatch("cars", {
@@ -48,6 +56,10 @@ function onMessage(message) {
         data,
       });
     }, 1000);
+  } else if (!Array.isArray(data)) {
+    // After the first, our own car comes back every tick as the server sees it.
+    const dispatch = createEventDispatcher(document.getElementById("canvas"));
+    dispatch("player", data);
   } else {
     const dispatch = createEventDispatcher(document.getElementById("canvas"));
     dispatch("cars", {
@@ -79,7 +91,13 @@ function onError(e) {
 }
 
 async function createConnection() {
-  ws = new WebSocket("ws://localhost:8080/");
+  // Pass `?vehicle=<class>` on the page URL to pick what to drive.
+  const vehicle = new URLSearchParams(window.location.search).get("vehicle");
+  ws = new WebSocket(
+    vehicle
+      ? `ws://localhost:8080/?vehicle=${encodeURIComponent(vehicle)}`
+      : "ws://localhost:8080/"
+  );
   ws.onopen = onOpen;
   ws.onclose = onClose;
   ws.onmessage = onMessage;